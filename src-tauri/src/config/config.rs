@@ -6,9 +6,99 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use crate::{error::LinkorgError, file::format::note_extensions};
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Config {
-    pub notes_dir: PathBuf,
+    // Older configs only have a single notes directory, it is read as the
+    // first source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub ratings: RatingScale,
+    #[serde(default)]
+    pub sources: Vec<Source>,
+}
+
+// A directory of notes. `max_depth` is how many folders deep below `path` notes
+// are looked for, with no limit by default. The globs are matched against the
+// path relative to `path`, and `default_format` is the extension of the notes
+// created in it. Symlinked notes and folders are only read with
+// `follow_links`, `.gitignore` and `.linkorgignore` files are honoured unless
+// `ignore_files` is turned off, and `default_excludes` skips the folders and
+// files which are never notes, like `.git` or Emacs backups.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Source {
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default = "default_format")]
+    pub default_format: String,
+    #[serde(default)]
+    pub follow_links: bool,
+    #[serde(default = "enabled")]
+    pub ignore_files: bool,
+    #[serde(default = "enabled")]
+    pub default_excludes: bool,
+}
+
+fn default_format() -> String {
+    "org".to_string()
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Source {
+    pub fn new(name: &str, path: PathBuf) -> Source {
+        Source {
+            name: name.to_string(),
+            path,
+            max_depth: None,
+            include: vec![],
+            exclude: vec![],
+            default_format: default_format(),
+            follow_links: false,
+            ignore_files: true,
+            default_excludes: true,
+        }
+    }
+}
+
+// The likeability labels from the most liked to the least liked one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct RatingScale(pub Vec<String>);
+
+impl Default for RatingScale {
+    fn default() -> Self {
+        RatingScale(vec![
+            "Must read".to_string(),
+            "Good read".to_string(),
+            "Mediocre read".to_string(),
+        ])
+    }
+}
+
+impl RatingScale {
+    // Position of a label on the scale, ignoring case and extra whitespace.
+    pub fn rank(&self, label: &str) -> Option<usize> {
+        let normalise = |label: &str| {
+            label
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        };
+        let label = normalise(label);
+        self.0.iter().position(|rating| normalise(rating) == label)
+    }
 }
 
 fn expand_tilde(path: &PathBuf) -> Option<PathBuf> {
@@ -30,30 +120,141 @@ fn expand_tilde(path: &PathBuf) -> Option<PathBuf> {
     })
 }
 
-fn create_config(config_path: &PathBuf) {
-    let config_dir = config_path
-        .parent()
-        .expect("Unable to get the parent directory from config path");
+fn create_config(config_path: &PathBuf) -> Result<(), LinkorgError> {
+    let config_dir = config_path.parent().ok_or_else(|| {
+        LinkorgError::ConfigParse(
+            config_path.to_path_buf(),
+            "Unable to get the parent directory from config path".to_string(),
+        )
+    })?;
     let _ = std::fs::create_dir_all(config_dir);
-    let default_config = toml::to_string(&Config{
-        notes_dir: dirs::home_dir().unwrap().join("Documents").join(env!("CARGO_PKG_NAME")),
-    }).unwrap();
-    let mut config_file = std::fs::File::create(config_path).expect("Unable to create config file");
+    let home_dir = dirs::home_dir().ok_or_else(|| {
+        LinkorgError::ConfigParse(
+            config_path.to_path_buf(),
+            "Unable to get the home directory".to_string(),
+        )
+    })?;
+    let default_config = toml::to_string(&Config {
+        notes_dir: None,
+        ratings: RatingScale::default(),
+        sources: vec![Source::new(
+            "Notes",
+            home_dir.join("Documents").join(env!("CARGO_PKG_NAME")),
+        )],
+    })
+    .map_err(|err| LinkorgError::ConfigParse(config_path.to_path_buf(), err.to_string()))?;
+    let mut config_file = std::fs::File::create(config_path)
+        .map_err(|err| LinkorgError::Io(config_path.to_path_buf(), err))?;
     config_file
         .write_all(default_config.as_bytes())
-        .expect("Unable to write default config to the config file");
+        .map_err(|err| LinkorgError::Io(config_path.to_path_buf(), err))
 }
 
-pub fn parse_config(config_path: &PathBuf) -> Config {
+pub fn parse_config(config_path: &PathBuf) -> Result<Config, LinkorgError> {
     if !config_path.exists() {
-        create_config(&config_path);
+        create_config(config_path)?;
+    }
+
+    let config_str = fs::read_to_string(config_path)
+        .map_err(|err| LinkorgError::Io(config_path.to_path_buf(), err))?;
+    let mut config: Config = toml::from_str(config_str.as_str())
+        .map_err(|err| LinkorgError::ConfigParse(config_path.to_path_buf(), err.to_string()))?;
+
+    if let Some(notes_dir) = config.notes_dir.take() {
+        config.sources.insert(0, Source::new("Notes", notes_dir));
+    }
+    let extensions = note_extensions();
+    for source in &mut config.sources {
+        source.path = expand_tilde(&source.path).ok_or_else(|| {
+            LinkorgError::ConfigParse(
+                config_path.to_path_buf(),
+                format!("Unable to expand ~ in the path of {}", source.name),
+            )
+        })?;
+        if !extensions.contains(&source.default_format.as_str()) {
+            return Err(LinkorgError::ConfigParse(
+                config_path.to_path_buf(),
+                format!(
+                    "Unknown default_format {} of {}, it has to be one of {}",
+                    source.default_format,
+                    source.name,
+                    extensions.join(", ")
+                ),
+            ));
+        }
     }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_config_with_notes_dir_and_sources() {
+        let dir = std::env::temp_dir().join("linkorg_parsing_config_with_sources");
+        let _ = fs::create_dir_all(&dir);
+        let config_path = dir.join("config.toml");
+        fs::write(
+            &config_path,
+            r#"notes_dir = "/notes"
+
+[[sources]]
+name = "Shared"
+path = "/shared/reading"
+max_depth = 2
+exclude = ["archive/**"]
+default_format = "md"
+follow_links = true
+default_excludes = false
+"#,
+        )
+        .unwrap();
 
-    let mut config = match fs::read_to_string(config_path) {
-        Ok(val) => toml::from_str(val.as_str()).unwrap(),
-        Err(_) => Config::default(),
-    };
+        let config = parse_config(&config_path).unwrap();
+        assert_eq!(config.notes_dir, None);
+        assert_eq!(config.ratings, RatingScale::default());
+        assert_eq!(
+            config.sources,
+            vec![
+                Source::new("Notes", PathBuf::from("/notes")),
+                Source {
+                    name: "Shared".to_string(),
+                    path: PathBuf::from("/shared/reading"),
+                    max_depth: Some(2),
+                    include: vec![],
+                    exclude: vec!["archive/**".to_string()],
+                    default_format: "md".to_string(),
+                    follow_links: true,
+                    ignore_files: true,
+                    default_excludes: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn refusing_unknown_default_format() {
+        let dir = std::env::temp_dir().join("linkorg_refusing_unknown_default_format");
+        let _ = fs::create_dir_all(&dir);
+        let config_path = dir.join("config.toml");
+        fs::write(
+            &config_path,
+            "[[sources]]\nname = \"Notes\"\npath = \"/notes\"\ndefault_format = \"docx\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            parse_config(&config_path),
+            Err(LinkorgError::ConfigParse(_, message)) if message.contains("docx")
+        ));
+    }
 
-    config.notes_dir = expand_tilde(&config.notes_dir).unwrap();
-    return config;
+    #[test]
+    fn creating_default_config() {
+        let dir = std::env::temp_dir().join("linkorg_creating_default_config");
+        let _ = fs::remove_dir_all(&dir);
+        let config = parse_config(&dir.join("config.toml")).unwrap();
+        assert_eq!(config.sources.len(), 1);
+        assert_eq!(config.sources[0].name, "Notes");
+    }
 }
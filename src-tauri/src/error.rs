@@ -0,0 +1,105 @@
+use serde::{Serialize, Serializer};
+use std::{fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum LinkorgError {
+    Io(PathBuf, io::Error),
+    UnsupportedFormat(PathBuf),
+    InvalidUtf8(PathBuf, usize),
+    ConfigParse(PathBuf, String),
+    Watch(PathBuf, String),
+    InvalidGlob(String, String),
+    MissingLine(usize),
+    LineChanged(usize),
+    NotAHeading(usize),
+    UnrecognisedLine(String),
+    NoSibling(String),
+    TopLevelHeading(String),
+    UnsupportedEdit(&'static str),
+    UnknownSource(String),
+    InvalidNoteName(String),
+    NoteExists(PathBuf),
+    NotANote(PathBuf),
+    NotCanonical(usize),
+}
+
+impl fmt::Display for LinkorgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkorgError::Io(path, err) => {
+                write!(f, "Unable to access {} : {}", path.display(), err)
+            }
+            LinkorgError::UnsupportedFormat(path) => {
+                write!(f, "Not a supported file format {}", path.display())
+            }
+            LinkorgError::InvalidUtf8(path, line_number) => write!(
+                f,
+                "Line {} of {} is not valid UTF-8",
+                line_number,
+                path.display()
+            ),
+            LinkorgError::ConfigParse(path, message) => {
+                write!(f, "Unable to parse config {} : {}", path.display(), message)
+            }
+            LinkorgError::Watch(path, message) => {
+                write!(f, "Unable to watch {} : {}", path.display(), message)
+            }
+            LinkorgError::InvalidGlob(pattern, message) => {
+                write!(f, "Invalid glob {} : {}", pattern, message)
+            }
+            LinkorgError::MissingLine(line_number) => {
+                write!(f, "Line {} does not exist in the file", line_number)
+            }
+            LinkorgError::LineChanged(line_number) => write!(
+                f,
+                "Line {} has changed since it was read, refusing to overwrite it",
+                line_number
+            ),
+            LinkorgError::NotAHeading(line_number) => {
+                write!(f, "Line {} is no longer a heading", line_number)
+            }
+            LinkorgError::UnrecognisedLine(line) => {
+                write!(f, "The edited line would not be recognised : {}", line)
+            }
+            LinkorgError::NoSibling(title) => {
+                write!(f, "Heading {} has no sibling to move past", title)
+            }
+            LinkorgError::TopLevelHeading(title) => {
+                write!(f, "Heading {} can't be promoted above the top level", title)
+            }
+            LinkorgError::UnsupportedEdit(format) => {
+                write!(f, "This edit is not supported in {} notes", format)
+            }
+            LinkorgError::UnknownSource(name) => {
+                write!(f, "There is no source named {} in the config", name)
+            }
+            LinkorgError::InvalidNoteName(name) => {
+                write!(f, "{} can't be used as the name of a note", name)
+            }
+            LinkorgError::NoteExists(path) => {
+                write!(f, "A note already exists at {}", path.display())
+            }
+            LinkorgError::NotANote(path) => {
+                write!(f, "{} is not a note of any source", path.display())
+            }
+            LinkorgError::NotCanonical(line_number) => write!(
+                f,
+                "Line {} is not the canonical form of its links, refusing to write it",
+                line_number
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LinkorgError {}
+
+// Errors cross the tauri boundary as their message, which is what the
+// frontend shows in its `{:catch e}` blocks.
+impl Serialize for LinkorgError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::date::NoteDate;
+use super::parse::{FileData, Heading, Link, TodoState};
+
+// A heading still to do, along with the note and headings it is in.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AgendaItem {
+    pub file: PathBuf,
+    pub breadcrumb: Vec<String>,
+    pub title: String,
+    pub line_number: usize,
+    pub priority: Option<char>,
+    pub scheduled: Option<NoteDate>,
+    pub deadline: Option<NoteDate>,
+    pub links: Vec<Link>,
+}
+
+impl AgendaItem {
+    // The earlier of the scheduled and deadline timestamps.
+    fn due(&self) -> Option<NoteDate> {
+        match (self.scheduled, self.deadline) {
+            (Some(scheduled), Some(deadline)) => Some(scheduled.min(deadline)),
+            (due, None) | (None, due) => due,
+        }
+    }
+}
+
+fn collect_todos(
+    file: &Path,
+    breadcrumb: &mut Vec<String>,
+    headings: &[Heading],
+    items: &mut Vec<AgendaItem>,
+) {
+    for heading in headings {
+        if heading.state == Some(TodoState::Todo) {
+            items.push(AgendaItem {
+                file: file.to_path_buf(),
+                breadcrumb: breadcrumb.clone(),
+                title: heading.title.clone(),
+                line_number: heading.line_number,
+                priority: heading.priority,
+                scheduled: heading.scheduled,
+                deadline: heading.deadline,
+                links: heading.links.clone(),
+            });
+        }
+        breadcrumb.push(heading.title.clone());
+        collect_todos(file, breadcrumb, &heading.heading, items);
+        breadcrumb.pop();
+    }
+}
+
+// Every TODO heading of the notes, the ones due first at the top. Headings
+// without a date come after the dated ones, and within the same date the higher
+// priority goes first, a heading without a cookie counting as `B` like in org.
+pub fn agenda<'a>(notes: impl IntoIterator<Item = (&'a Path, &'a FileData)>) -> Vec<AgendaItem> {
+    let mut items = vec![];
+    for (file, data) in notes {
+        collect_todos(file, &mut vec![], &data.heading, &mut items);
+    }
+    items.sort_by(|first, second| {
+        let key = |item: &AgendaItem| {
+            (
+                item.due().is_none(),
+                item.due(),
+                item.priority.unwrap_or('B'),
+            )
+        };
+        key(first).cmp(&key(second))
+    });
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::RatingScale;
+    use crate::file::format::{org::Org, NoteFormat};
+
+    #[test]
+    fn ordering_agenda() {
+        let file = PathBuf::from("/notes/books.org");
+        let lines: Vec<String> = "* Reading\n** TODO Someday\n** TODO [#A] Urgent without date\n** DONE Finished SCHEDULED: <2023-01-01 Sun>\n** TODO [#C] Read Kafka book SCHEDULED: <2024-01-01 Mon>\n[[Link][Kafka]]\n** TODO [#A] Read Rust book\nDEADLINE: <2024-01-01 Mon> SCHEDULED: <2024-02-01 Thu>\n*** TODO Early chapter\nSCHEDULED: <2023-12-01 Fri>"
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        let data = Org.parse("books.org".to_string(), &lines, &RatingScale::default());
+
+        let items = agenda([(file.as_path(), &data)]);
+        let titles: Vec<&str> = items.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Early chapter",
+                "Read Rust book",
+                "Read Kafka book",
+                "Urgent without date",
+                "Someday",
+            ]
+        );
+        assert_eq!(items[1].deadline, NoteDate::parse("<2024-01-01 Mon>"));
+        assert_eq!(items[1].scheduled, NoteDate::parse("<2024-02-01 Thu>"));
+        assert_eq!(items[2].priority, Some('C'));
+        assert_eq!(items[2].links[0].name, "Kafka");
+        assert_eq!(items[0].breadcrumb, vec!["Reading", "Read Rust book"]);
+    }
+}
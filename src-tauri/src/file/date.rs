@@ -0,0 +1,202 @@
+use chrono::{Local, NaiveDate, NaiveTime, Timelike};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+lazy_static! {
+    // A date, optionally followed by a weekday and a time of day which can end
+    // in the end time of a range, as in `2024-01-01 Mon 10:00-12:00`, or the ISO
+    // `2024-01-01T10:00:00` where a `-` starts the UTC offset instead.
+    static ref RE_MOMENT: Regex = Regex::new(
+        r"(?P<date>\d{4}-\d{2}-\d{2})(?:(?P<iso>T)|\s+(?:[^\d\s<>\[\]]+\s+)?)?(?P<time>\d{1,2}:\d{2}(?::\d{2})?)?(?:-(?P<end>\d{1,2}:\d{2}))?"
+    )
+    .unwrap();
+}
+
+// A day, with the time of day when one was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Moment {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+impl Moment {
+    fn parse(date: &str, time: Option<&str>) -> Option<Moment> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+        let time = match time {
+            Some(time) => Some(
+                NaiveTime::parse_from_str(time, "%H:%M:%S")
+                    .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+                    .ok()?,
+            ),
+            None => None,
+        };
+        Some(Moment { date, time })
+    }
+}
+
+impl fmt::Display for Moment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.date.format("%Y-%m-%d"))?;
+        match self.time {
+            Some(time) if time.second() != 0 => write!(f, "T{}", time.format("%H:%M:%S")),
+            Some(time) => write!(f, "T{}", time.format("%H:%M")),
+            None => Ok(()),
+        }
+    }
+}
+
+// A date written in a note, either as an org timestamp like
+// `[2023-07-10 Mon 17:00]` or `<2024-01-01 Mon>--<2024-01-03 Wed>`, or in ISO
+// 8601. It is sent to the frontend in ISO 8601, a range as `start/end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NoteDate {
+    pub start: Moment,
+    pub end: Option<Moment>,
+}
+
+impl NoteDate {
+    // The current day in the local time zone.
+    pub fn today() -> NoteDate {
+        NoteDate {
+            start: Moment {
+                date: Local::now().date_naive(),
+                time: None,
+            },
+            end: None,
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<NoteDate> {
+        let mut moments = RE_MOMENT.captures_iter(text);
+        let first = moments.next()?;
+        let time = first.name("time").map(|m| m.as_str());
+        let start = Moment::parse(&first["date"], time)?;
+        let end_time = first
+            .name("end")
+            .filter(|_| time.is_some() && first.name("iso").is_none());
+        let end = match end_time {
+            Some(end_time) => Some(Moment::parse(&first["date"], Some(end_time.as_str()))?),
+            None => match moments.next() {
+                Some(second) => {
+                    let between =
+                        &text[first.get(0).unwrap().end()..second.get(0).unwrap().start()];
+                    if !between.contains("--") && !between.contains('/') {
+                        return None;
+                    }
+                    Some(Moment::parse(
+                        &second["date"],
+                        second.name("time").map(|m| m.as_str()),
+                    )?)
+                }
+                None => None,
+            },
+        };
+        Some(NoteDate { start, end })
+    }
+}
+
+impl fmt::Display for NoteDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.start)?;
+        match self.end {
+            Some(end) => write!(f, "/{}", end),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Serialize for NoteDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for NoteDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        NoteDate::parse(&text).ok_or_else(|| de::Error::custom(format!("invalid date {}", text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moment(date: &str, time: Option<&str>) -> Moment {
+        Moment::parse(date, time).unwrap()
+    }
+
+    #[test]
+    fn parsing_org_timestamps() {
+        assert_eq!(
+            NoteDate::parse("[2023-07-10 Mon 17:00]"),
+            Some(NoteDate {
+                start: moment("2023-07-10", Some("17:00")),
+                end: None,
+            })
+        );
+        assert_eq!(
+            NoteDate::parse("<2024-01-01 Mon +1w>"),
+            Some(NoteDate {
+                start: moment("2024-01-01", None),
+                end: None,
+            })
+        );
+        assert_eq!(
+            NoteDate::parse("<2024-01-01 Mon 10:00-12:30>"),
+            Some(NoteDate {
+                start: moment("2024-01-01", Some("10:00")),
+                end: Some(moment("2024-01-01", Some("12:30"))),
+            })
+        );
+        assert_eq!(
+            NoteDate::parse("<2024-01-01 Mon>--<2024-01-03 Wed>"),
+            Some(NoteDate {
+                start: moment("2024-01-01", None),
+                end: Some(moment("2024-01-03", None)),
+            })
+        );
+        assert_eq!(NoteDate::parse("[2023-02-30 Thu]"), None);
+        assert_eq!(NoteDate::parse("No creation Date found"), None);
+    }
+
+    #[test]
+    fn parsing_iso_dates() {
+        assert_eq!(
+            NoteDate::parse("2023-07-10"),
+            Some(NoteDate {
+                start: moment("2023-07-10", None),
+                end: None,
+            })
+        );
+        assert_eq!(
+            NoteDate::parse("1979-05-27T07:32:10-08:00"),
+            Some(NoteDate {
+                start: moment("1979-05-27", Some("07:32:10")),
+                end: None,
+            })
+        );
+    }
+
+    #[test]
+    fn serializing_in_iso_8601() {
+        for (text, iso) in [
+            ("[2023-07-10 Mon 17:00]", "2023-07-10T17:00"),
+            ("2023-07-10T17:00:05", "2023-07-10T17:00:05"),
+            (
+                "<2024-01-01 Mon>--<2024-01-03 Wed>",
+                "2024-01-01/2024-01-03",
+            ),
+        ] {
+            let date = NoteDate::parse(text).unwrap();
+            let json = serde_json::to_string(&date).unwrap();
+            assert_eq!(json, format!("\"{}\"", iso));
+            assert_eq!(serde_json::from_str::<NoteDate>(&json).unwrap(), date);
+        }
+        assert!(
+            NoteDate::parse("2024-01-01").unwrap() < NoteDate::parse("2024-01-01 10:00").unwrap()
+        );
+    }
+}
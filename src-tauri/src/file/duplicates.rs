@@ -0,0 +1,335 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use super::parse::FileData;
+use super::search::{note_links, LinkEntry};
+
+// Query parameters which only track where a visitor came from, on any site.
+const TRACKING_PARAMS: [&str; 7] = [
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid",
+];
+const TRACKING_PREFIXES: [&str; 1] = ["utm_"];
+// The ones Amazon adds, which can mean something else on other sites, like the
+// branch in GitHub's `?ref=`.
+const AMAZON_TRACKING_PARAMS: [&str; 6] = ["ref", "ref_", "psc", "content-id", "smid", "_encoding"];
+const AMAZON_TRACKING_PREFIXES: [&str; 2] = ["pd_rd_", "pf_rd_"];
+
+// How alike two names have to be for their links to be duplicates, 1 being
+// the same name.
+const NAME_SIMILARITY: f64 = 0.85;
+
+lazy_static! {
+    static ref RE_SCHEME: Regex = Regex::new(r"^[a-zA-Z][\w+.-]*://").unwrap();
+    // An Amazon product page up to its ASIN, which is all it takes to find it.
+    static ref RE_AMAZON: Regex = Regex::new(r"^(?P<product>(?i:(?:https?://)?(?:[\w-]+\.)*amazon\.[a-z.]+)/(?:[^/?#]+/)?(?:dp|gp/product)/(?P<asin>[A-Z0-9]{10}))(?:[/?#]|$)").unwrap();
+}
+
+// Links which look like the same thing. `url` is the address they all share
+// once normalised, clusters joined by their names alone have none.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct DuplicateCluster {
+    pub url: Option<String>,
+    pub links: Vec<LinkEntry>,
+}
+
+// The host of an address in lowercase, without its scheme and `www.`.
+fn host_of(url: &str) -> String {
+    let url = RE_SCHEME.replace(url, "");
+    let host = url.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.to_lowercase();
+    host.strip_prefix("www.").unwrap_or(&host).to_string()
+}
+
+fn is_amazon(host: &str) -> bool {
+    host.split('.').any(|label| label == "amazon")
+}
+
+fn is_tracking_param(host: &str, param: &str) -> bool {
+    let key = param.split('=').next().unwrap_or(param).to_lowercase();
+    let tracks = |params: &[&str], prefixes: &[&str]| {
+        params.contains(&key.as_str()) || prefixes.iter().any(|prefix| key.starts_with(prefix))
+    };
+    tracks(&TRACKING_PARAMS, &TRACKING_PREFIXES)
+        || (is_amazon(host) && tracks(&AMAZON_TRACKING_PARAMS, &AMAZON_TRACKING_PREFIXES))
+}
+
+// The address of a link without its scheme, `www.`, fragment, trailing slash
+// and tracking parameters, with the host in lowercase and the other parameters
+// sorted, so that different ways of writing the same address compare equal.
+// Amazon product pages come down to their host and ASIN.
+pub fn normalise_url(url: &str) -> String {
+    let url = url.trim();
+    if let Some(product) = RE_AMAZON.captures(url) {
+        return format!("{}/dp/{}", host_of(url), &product["asin"]);
+    }
+    let url = url.split('#').next().unwrap_or(url);
+    let (url, query) = url.split_once('?').unwrap_or((url, ""));
+    let host = host_of(url);
+    let url = RE_SCHEME.replace(url, "");
+    let path = &url[url.find('/').unwrap_or(url.len())..];
+
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|param| !param.is_empty() && !is_tracking_param(&host, param))
+        .collect();
+    params.sort_unstable();
+
+    let mut normalised = format!("{}{}", host, path.trim_end_matches('/'));
+    if !params.is_empty() {
+        normalised.push('?');
+        normalised.push_str(&params.join("&"));
+    }
+    normalised
+}
+
+// The address of a link the way it is best written down: without tracking
+// parameters, and an Amazon product page without anything after its ASIN.
+// Unlike normalise_url the result is still an address which can be opened.
+pub fn canonical_url(url: &str) -> String {
+    if let Some(product) = RE_AMAZON.captures(url) {
+        return product["product"].to_string();
+    }
+    let (url, fragment) = match url.split_once('#') {
+        Some((url, fragment)) => (url, Some(fragment)),
+        None => (url, None),
+    };
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let host = host_of(base);
+    let params: Vec<&str> = query
+        .split('&')
+        .filter(|param| !param.is_empty() && !is_tracking_param(&host, param))
+        .collect();
+
+    let mut canonical = base.to_string();
+    if !params.is_empty() {
+        canonical.push('?');
+        canonical.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        canonical.push('#');
+        canonical.push_str(fragment);
+    }
+    canonical
+}
+
+fn normalise_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Whether two normalised names are at most a few typos apart.
+fn similar_names(a: &[char], b: &[char]) -> bool {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return false;
+    }
+    // Names of too different a length can't be similar enough.
+    let allowed = (longest as f64 * (1.0 - NAME_SIMILARITY)).floor() as usize;
+    if a.len().abs_diff(b.len()) > allowed {
+        return false;
+    }
+    edit_distance(a, b) <= allowed
+}
+
+fn root(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+fn join(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (root(parents, a), root(parents, b));
+    parents[a.max(b)] = a.min(b);
+}
+
+// Groups of links across the notes which share an address once normalised or
+// whose names are nearly the same, in the order they are written in. Links
+// without a name of their own are only matched on their address.
+pub fn find_duplicates<'a>(
+    notes: impl IntoIterator<Item = (&'a Path, &'a FileData)>,
+) -> Vec<DuplicateCluster> {
+    let entries = note_links(notes);
+    let urls: Vec<String> = entries
+        .iter()
+        .map(|entry| normalise_url(&entry.link.link))
+        .collect();
+    let mut parents: Vec<usize> = (0..entries.len()).collect();
+
+    let mut by_url: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, url) in urls.iter().enumerate() {
+        if url.is_empty() {
+            continue;
+        }
+        if let Some(&first) = by_url.get(url.as_str()) {
+            join(&mut parents, first, i);
+        } else {
+            by_url.insert(url, i);
+        }
+    }
+
+    let names: Vec<(usize, Vec<char>, HashSet<String>)> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.link.name != entry.link.link)
+        .map(|(i, entry)| {
+            let name = normalise_name(&entry.link.name);
+            let words = name.split(' ').map(|word| word.to_string()).collect();
+            (i, name.chars().collect(), words)
+        })
+        .collect();
+    for (n, (a, a_name, a_words)) in names.iter().enumerate() {
+        for (b, b_name, b_words) in &names[n + 1..] {
+            // Only names sharing a word are compared letter by letter.
+            if !a_words.is_disjoint(b_words) && similar_names(a_name, b_name) {
+                join(&mut parents, *a, *b);
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..entries.len() {
+        let cluster = root(&mut parents, i);
+        clusters.entry(cluster).or_default().push(i);
+    }
+    clusters
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| DuplicateCluster {
+            url: Some(urls[members[0]].clone())
+                .filter(|url| members.iter().all(|&i| urls[i] == *url)),
+            links: members.iter().map(|&i| entries[i].clone()).collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::RatingScale;
+    use crate::file::format::{markdown::Markdown, org::Org, NoteFormat};
+    use std::path::PathBuf;
+
+    #[test]
+    fn normalising_urls() {
+        assert_eq!(
+            normalise_url("https://www.amazon.in/Subtle-Art/dp/0062641549/"),
+            "amazon.in/dp/0062641549"
+        );
+        assert_eq!(
+            normalise_url("https://www.amazon.in/EVERYTHING-FUCKED-Mark-Manson/dp/B08QHQ7WN5/ref=d_zg-te-pba_sccl_3_6/260-6159620-1314115?pd_rd_w=Nt2LI&psc=1"),
+            normalise_url("https://amazon.in/dp/B08QHQ7WN5?psc=1")
+        );
+        assert_eq!(
+            normalise_url("http://WWW.Example.com/page?utm_source=x&b=2&fbclid=Nt2LI&a=1#top"),
+            "example.com/page?a=1&b=2"
+        );
+        assert_eq!(normalise_url("Link to test book"), "link to test book");
+        assert_eq!(
+            normalise_url("https://github.com/owner/repo?ref=main&utm_campaign=x"),
+            "github.com/owner/repo?ref=main"
+        );
+        assert_eq!(
+            normalise_url("https://www.amazon.com/s?k=rust&ref=nb_sb&psc=1"),
+            "amazon.com/s?k=rust"
+        );
+    }
+
+    #[test]
+    fn canonicalising_urls() {
+        assert_eq!(
+            canonical_url("https://www.amazon.in/EVERYTHING-FUCKED-Mark-Manson/dp/B08QHQ7WN5/ref=d_zg-te-pba_sccl_3_6/260-6159620-1314115?pd_rd_w=Nt2LI&content-id=amzn1.sym.19fd9d5f&pf_rd_p=19fd9d5f&pd_rd_i=B08QHQ7WN5&psc=1"),
+            "https://www.amazon.in/EVERYTHING-FUCKED-Mark-Manson/dp/B08QHQ7WN5"
+        );
+        assert_eq!(
+            canonical_url("https://amazon.com/gp/product/0062641549?ref_=nav"),
+            "https://amazon.com/gp/product/0062641549"
+        );
+        assert_eq!(
+            canonical_url("https://www.amazon.in/Book/dp/B0ABCDEFGHIJ?psc=1"),
+            "https://www.amazon.in/Book/dp/B0ABCDEFGHIJ"
+        );
+        assert_eq!(
+            canonical_url("https://example.com/page?id=3&utm_source=feed#notes"),
+            "https://example.com/page?id=3#notes"
+        );
+        assert_eq!(
+            canonical_url("https://example.com/?utm_medium=x"),
+            "https://example.com/"
+        );
+        assert_eq!(canonical_url("Link to test book"), "Link to test book");
+    }
+
+    #[test]
+    fn matching_names() {
+        let chars = |name: &str| normalise_name(name).chars().collect::<Vec<_>>();
+        assert!(similar_names(
+            &chars("The Subtle Art of Not Giving a F*ck"),
+            &chars("the subtle art of not giving a fck")
+        ));
+        assert!(similar_names(
+            &chars("Designing Data-Intensive Applications"),
+            &chars("Designing Data Intensive Aplications")
+        ));
+        assert!(!similar_names(&chars("Dune"), &chars("Dune Messiah")));
+    }
+
+    #[test]
+    fn finding_duplicates_across_notes() {
+        let ratings = RatingScale::default();
+        let lines =
+            |text: &str| -> Vec<String> { text.lines().map(|line| line.to_string()).collect() };
+        let books = Org.parse(
+            "books.org".to_string(),
+            &lines("* Self help\n[[https://www.amazon.in/Subtle-Art/dp/0062641549?ref_=nav&pd_rd_w=x][Subtle Art]]\n[[https://dune.example][Dune]]\n* Fiction\n[[https://example.com/ddia][Designing Data-Intensive Applications]]"),
+            &ratings,
+        );
+        let reading = Markdown.parse(
+            "reading.md".to_string(),
+            &lines("[Subtle art of not giving](http://amazon.in/Subtle-Art/dp/0062641549/)\n[Designing Data Intensive Aplications](https://other.example/ddia)\n[Dune Messiah](https://messiah.example)"),
+            &ratings,
+        );
+        let (books_path, reading_path) = (PathBuf::from("books.org"), PathBuf::from("reading.md"));
+        let clusters = find_duplicates([
+            (books_path.as_path(), &books),
+            (reading_path.as_path(), &reading),
+        ]);
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].url.as_deref(), Some("amazon.in/dp/0062641549"));
+        let places: Vec<(&Path, usize)> = clusters[0]
+            .links
+            .iter()
+            .map(|entry| (entry.file.as_path(), entry.link.line_number))
+            .collect();
+        assert_eq!(
+            places,
+            vec![(books_path.as_path(), 2), (reading_path.as_path(), 1)]
+        );
+        assert_eq!(clusters[0].links[0].breadcrumb, vec!["Self help"]);
+
+        assert_eq!(clusters[1].url, None);
+        assert_eq!(clusters[1].links.len(), 2);
+        assert_eq!(clusters[1].links[1].link.line_number, 2);
+    }
+}
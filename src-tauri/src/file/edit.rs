@@ -0,0 +1,1601 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::LinkorgError;
+
+use crate::config::config::RatingScale;
+
+use super::date::NoteDate;
+use super::duplicates::canonical_url;
+use super::format::{format_of, NoteFormat};
+use super::parse::{read_data_file, FileData, Heading, LineType, Link, LinkSpan, Progress};
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LinkUpdate {
+    pub name: String,
+    pub link: String,
+    pub read_till: Option<Progress>,
+    pub description: Option<String>,
+    pub likeability: Option<String>,
+    #[serde(default)]
+    pub added: Option<NoteDate>,
+    #[serde(default)]
+    pub finished: Option<NoteDate>,
+}
+
+impl From<&Link> for LinkUpdate {
+    fn from(link: &Link) -> Self {
+        LinkUpdate {
+            name: link.name.clone(),
+            link: link.link.clone(),
+            read_till: link.read_till.clone(),
+            description: link.description.clone(),
+            likeability: link.likeability.as_ref().map(|val| val.label.clone()),
+            added: link.added,
+            finished: link.finished,
+        }
+    }
+}
+
+impl LinkUpdate {
+    // Dates the link with `today`: a new link is added today and a link which
+    // is done but wasn't before is finished today, unless the dates were given.
+    pub(crate) fn dated(mut self, original: Option<&Link>, today: NoteDate) -> LinkUpdate {
+        match original {
+            None => {
+                self.added.get_or_insert(today);
+            }
+            Some(original) => {
+                if self.read_till == Some(Progress::Done)
+                    && original.read_till != Some(Progress::Done)
+                {
+                    self.finished.get_or_insert(today);
+                }
+            }
+        }
+        self
+    }
+
+    // The read_till annotation, followed by the day the link was finished on
+    // once it is done.
+    fn read_till_annotation(&self) -> Option<String> {
+        let annotation = self.read_till.as_ref()?.annotation();
+        match (&self.read_till, self.finished) {
+            (Some(Progress::Done), Some(finished)) => Some(format!("{} {}", annotation, finished)),
+            _ => Some(annotation),
+        }
+    }
+
+    fn added_annotation(&self) -> Option<String> {
+        self.added.map(|added| format!("-- added {}", added))
+    }
+}
+
+// Everything needed to read and write the lines of one note file.
+pub(crate) struct Dialect<'a> {
+    pub(crate) format: &'static dyn NoteFormat,
+    pub(crate) ratings: &'a RatingScale,
+}
+
+impl<'a> Dialect<'a> {
+    pub(crate) fn of(path: &Path, ratings: &'a RatingScale) -> Result<Dialect<'a>, LinkorgError> {
+        let format =
+            format_of(path).ok_or_else(|| LinkorgError::UnsupportedFormat(path.to_path_buf()))?;
+        Ok(Dialect { format, ratings })
+    }
+
+    fn classify(&self, line: &str, line_number: usize) -> Option<LineType> {
+        self.format.classify_line(line, line_number, self.ratings)
+    }
+
+    // Classifies the line at `index` of the file, which can depend on the
+    // line after it. The metadata block at the top of a note holds nothing.
+    fn classify_at(&self, lines: &[String], index: usize) -> Option<LineType> {
+        if index < self.format.body_start(lines) {
+            return None;
+        }
+        let line = lines.get(index)?;
+        let next = lines.get(index + 1).map(|next| next.as_str());
+        self.format
+            .classify_with_next(line, next, index + 1, self.ratings)
+    }
+
+    fn find_links(&self, line: &str, line_number: usize) -> Vec<LinkSpan> {
+        self.format.find_links(line, line_number, self.ratings)
+    }
+}
+
+struct FileLines {
+    lines: Vec<String>,
+    line_ending: &'static str,
+    trailing_newline: bool,
+}
+
+impl FileLines {
+    fn read(path: &Path) -> Result<FileLines, LinkorgError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| LinkorgError::Io(path.to_path_buf(), err))?;
+        let line_ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        Ok(FileLines {
+            lines: content.lines().map(|line| line.to_string()).collect(),
+            line_ending,
+            trailing_newline: content.ends_with('\n'),
+        })
+    }
+
+    fn write(&self, path: &Path) -> Result<(), LinkorgError> {
+        let mut content = self.lines.join(self.line_ending);
+        if self.trailing_newline {
+            content.push_str(self.line_ending);
+        }
+        fs::write(path, content).map_err(|err| LinkorgError::Io(path.to_path_buf(), err))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellRole {
+    Link,
+    Description,
+    Likeability,
+    ReadTill,
+    Added,
+    Empty,
+    Other,
+}
+
+impl CellRole {
+    // A parenthesised cell is a likeability the same way an annotation after a
+    // link is, when it is on the rating scale or ends in "read".
+    fn classify(cell: &str, dialect: &Dialect) -> CellRole {
+        let cell = cell.trim();
+        let parenthesised = cell
+            .strip_prefix('(')
+            .and_then(|cell| cell.strip_suffix(')'))
+            .map(str::trim);
+        if cell.is_empty() {
+            CellRole::Empty
+        } else if !dialect.find_links(cell, 0).is_empty() {
+            CellRole::Link
+        } else if cell.starts_with("--") && cell[2..].trim_start().starts_with("added") {
+            CellRole::Added
+        } else if cell.starts_with("--") {
+            CellRole::ReadTill
+        } else if parenthesised.is_some_and(|text| {
+            dialect.ratings.rank(text).is_some() || text.to_lowercase().ends_with("read")
+        }) {
+            CellRole::Likeability
+        } else if cell.starts_with('(') {
+            CellRole::Description
+        } else {
+            CellRole::Other
+        }
+    }
+
+    fn rank(&self) -> usize {
+        match self {
+            CellRole::Link => 0,
+            CellRole::Description => 1,
+            CellRole::Likeability => 2,
+            CellRole::ReadTill => 3,
+            CellRole::Added => 4,
+            CellRole::Empty | CellRole::Other => 0,
+        }
+    }
+}
+
+fn cell_content(role: CellRole, format: &dyn NoteFormat, update: &LinkUpdate) -> Option<String> {
+    match role {
+        CellRole::Link => Some(format.link_markup(&update.link, &update.name)),
+        CellRole::Description => update.description.as_ref().map(|val| format!("({})", val)),
+        CellRole::Likeability => update.likeability.as_ref().map(|val| format!("({})", val)),
+        CellRole::ReadTill => update.read_till_annotation(),
+        CellRole::Added => update.added_annotation(),
+        CellRole::Empty | CellRole::Other => None,
+    }
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+// Whether the text in front of a link is nothing but a list bullet, if any.
+fn is_list_marker(prefix: &str) -> bool {
+    prefix
+        .trim()
+        .chars()
+        .all(|c| "-+*.)".contains(c) || c.is_ascii_digit())
+}
+
+// Only rows closed by a `|` as well, AsciiDoc rows are edited like any other line.
+pub(crate) fn is_table_row(line: &str) -> bool {
+    let line = line.trim();
+    line.len() > 1 && line.starts_with('|') && line.ends_with('|')
+}
+
+// Rebuilds a table row using `template` for the cell layout, keeping every
+// cell at least as wide as it was so the table stays aligned.
+fn format_table_row(template: &str, dialect: &Dialect, update: &LinkUpdate) -> String {
+    let format = dialect.format;
+    let body = template.trim();
+    let body = body.strip_prefix('|').unwrap_or(body);
+    let body = body.strip_suffix('|').unwrap_or(body);
+    let cells: Vec<&str> = body.split('|').collect();
+
+    let mut roles: Vec<CellRole> = cells
+        .iter()
+        .map(|cell| CellRole::classify(cell, dialect))
+        .collect();
+    // Empty cells stand for the optional columns which the row does not use,
+    // so hand them out in the usual column order.
+    let mut last_rank = 0;
+    for i in 0..roles.len() {
+        if roles[i] == CellRole::Empty {
+            if let Some(role) = [CellRole::Description, CellRole::Likeability]
+                .into_iter()
+                .find(|role| role.rank() > last_rank && !roles.contains(role))
+            {
+                roles[i] = role;
+            }
+        }
+        last_rank = last_rank.max(roles[i].rank());
+    }
+
+    let mut row: Vec<String> = cells
+        .iter()
+        .zip(roles.iter())
+        .map(|(cell, role)| match role {
+            CellRole::Empty | CellRole::Other => cell.to_string(),
+            role => {
+                let content = cell_content(*role, format, update).unwrap_or_default();
+                let width = cell.chars().count().saturating_sub(2);
+                format!(" {:<width$} ", content, width = width)
+            }
+        })
+        .collect();
+
+    for role in [
+        CellRole::Link,
+        CellRole::Description,
+        CellRole::Likeability,
+        CellRole::ReadTill,
+        CellRole::Added,
+    ] {
+        if roles.contains(&role) {
+            continue;
+        }
+        if let Some(content) = cell_content(role, format, update) {
+            let position = roles
+                .iter()
+                .position(|other| other.rank() > role.rank())
+                .unwrap_or(roles.len());
+            roles.insert(position, role);
+            row.insert(position, format!(" {} ", content));
+        }
+    }
+
+    format!("{}|{}|", leading_whitespace(template), row.join("|"))
+}
+
+fn pad_to_column(line: &mut String, column: Option<usize>) {
+    let len = line.chars().count();
+    match column {
+        Some(column) if column > len => line.push_str(&" ".repeat(column - len)),
+        _ => line.push(' '),
+    }
+}
+
+// Builds a link in its plain form after `prefix`, lining the annotations up
+// with the columns they occupy in `template` when there is one.
+fn format_plain_link(
+    prefix: &str,
+    template: Option<(&str, &LinkSpan)>,
+    format: &dyn NoteFormat,
+    update: &LinkUpdate,
+) -> String {
+    let column = |offset: fn(&LinkSpan) -> Option<usize>| {
+        let (line, span) = template?;
+        Some(line[..offset(span)?].chars().count())
+    };
+
+    let mut line = format!(
+        "{}{}",
+        prefix,
+        format.link_markup(&update.link, &update.name)
+    );
+    if let Some(description) = &update.description {
+        pad_to_column(&mut line, column(|span| span.description));
+        line.push_str(&format!("({})", description));
+    }
+    if let Some(likeability) = &update.likeability {
+        pad_to_column(&mut line, column(|span| span.likeability));
+        line.push_str(&format!("({})", likeability));
+    }
+    if let Some(read_till) = update.read_till_annotation() {
+        pad_to_column(&mut line, column(|span| span.read_till));
+        line.push_str(&read_till);
+    }
+    if let Some(added) = update.added_annotation() {
+        pad_to_column(&mut line, column(|span| span.added));
+        line.push_str(&added);
+    }
+    line
+}
+
+// Formats a new link line in the style of `template`, one of its siblings.
+pub(crate) fn format_link_line(
+    template: Option<&str>,
+    dialect: &Dialect,
+    update: &LinkUpdate,
+) -> String {
+    if let Some(template) = template.filter(|template| is_table_row(template)) {
+        return format_table_row(template, dialect, update);
+    }
+    let span = template.and_then(|template| dialect.find_links(template, 0).into_iter().next());
+    match (template, span) {
+        (Some(template), Some(span)) => {
+            // Carry over list bullets but not any text in front of the link
+            let prefix = &template[..span.start];
+            let prefix = if is_list_marker(prefix) {
+                prefix
+            } else {
+                leading_whitespace(template)
+            };
+            format_plain_link(prefix, Some((template, &span)), dialect.format, update)
+        }
+        _ => format_plain_link("", None, dialect.format, update),
+    }
+}
+
+fn check_new_line(
+    line: String,
+    line_number: usize,
+    dialect: &Dialect,
+) -> Result<String, LinkorgError> {
+    match dialect.classify(&line, line_number) {
+        Some(LineType::Links(_)) => Ok(line),
+        _ => Err(LinkorgError::UnrecognisedLine(line)),
+    }
+}
+
+// Makes sure the line at `original.line_number` still holds `original`, so
+// that edits made to the file since it was read are never clobbered. Returns
+// the index of the line, where the link sits on it and how many links it has.
+fn check_link_line(
+    lines: &[String],
+    original: &Link,
+    dialect: &Dialect,
+) -> Result<(usize, LinkSpan, usize), LinkorgError> {
+    let index = original.line_number.wrapping_sub(1);
+    let current = lines
+        .get(index)
+        .ok_or(LinkorgError::MissingLine(original.line_number))?;
+
+    if let Some(LineType::Links(_)) = dialect.classify(current, original.line_number) {
+        let spans = dialect.find_links(current, original.line_number);
+        let count = spans.len();
+        if let Some(span) = spans.into_iter().find(|span| span.link == *original) {
+            return Ok((index, span, count));
+        }
+    }
+    Err(LinkorgError::LineChanged(original.line_number))
+}
+
+fn update_link_lines(
+    lines: &mut [String],
+    original: &Link,
+    update: &LinkUpdate,
+    dialect: &Dialect,
+) -> Result<(), LinkorgError> {
+    let (index, span, count) = check_link_line(lines, original, dialect)?;
+    let current = &lines[index];
+
+    let new_line = if count == 1 && is_table_row(current) {
+        format_table_row(current, dialect, update)
+    } else {
+        let link = format_plain_link(
+            &current[..span.start],
+            Some((current, &span)),
+            dialect.format,
+            update,
+        );
+        format!("{}{}", link, &current[span.end..])
+            .trim_end()
+            .to_string()
+    };
+
+    lines[index] = check_new_line(new_line, original.line_number, dialect)?;
+    Ok(())
+}
+
+fn heading_at(
+    lines: &[String],
+    line_number: usize,
+    dialect: &Dialect,
+) -> Result<Heading, LinkorgError> {
+    let index = line_number.wrapping_sub(1);
+    if index >= lines.len() {
+        return Err(LinkorgError::MissingLine(line_number));
+    }
+    match dialect.classify_at(lines, index) {
+        Some(LineType::Heading(heading)) => Ok(heading),
+        _ => Err(LinkorgError::NotAHeading(line_number)),
+    }
+}
+
+// Returns the range of lines holding the links which sit directly under the
+// heading at `heading_line`, or under the file root when it is `None`.
+fn section_bounds(
+    lines: &[String],
+    heading_line: Option<usize>,
+    dialect: &Dialect,
+) -> Result<(usize, usize), LinkorgError> {
+    let start = match heading_line {
+        None => 0,
+        Some(line_number) => {
+            heading_at(lines, line_number, dialect)?;
+            line_number
+        }
+    };
+    let end = (start..lines.len())
+        .find(|&index| {
+            matches!(
+                dialect.classify_at(lines, index),
+                Some(LineType::Heading(_))
+            )
+        })
+        .unwrap_or(lines.len());
+    Ok((start, end))
+}
+
+fn add_link_lines(
+    lines: &mut Vec<String>,
+    heading_line: Option<usize>,
+    link: &LinkUpdate,
+    dialect: &Dialect,
+) -> Result<usize, LinkorgError> {
+    let (start, end) = section_bounds(lines, heading_line, dialect)?;
+
+    let last_sibling = (start..end)
+        .rev()
+        .find(|&index| matches!(dialect.classify_at(lines, index), Some(LineType::Links(_))));
+    let (template, index) = match last_sibling {
+        Some(index) => (Some(lines[index].as_str()), index + 1),
+        None => {
+            let index = (start..end)
+                .rev()
+                .find(|&index| !lines[index].trim().is_empty())
+                .map_or(start, |index| index + 1);
+            (None, index)
+        }
+    };
+
+    let new_line = format_link_line(template, dialect, link);
+    let new_line = check_new_line(new_line, index + 1, dialect)?;
+
+    lines.insert(index, new_line);
+    Ok(index + 1)
+}
+
+// Removes a link with its annotations, and its line when nothing but a list
+// bullet is left on it. The cells of a table row all belong to its one link, so
+// the row goes with it. Returns whether the line was removed.
+fn delete_link_lines(
+    lines: &mut Vec<String>,
+    original: &Link,
+    dialect: &Dialect,
+) -> Result<bool, LinkorgError> {
+    let (index, span, count) = check_link_line(lines, original, dialect)?;
+    let current = &lines[index];
+    let (before, after) = (&current[..span.start], current[span.end..].trim_start());
+    if (count == 1 && is_table_row(current)) || (is_list_marker(before) && after.is_empty()) {
+        lines.remove(index);
+        return Ok(true);
+    }
+
+    lines[index] = if before.trim().is_empty() {
+        format!("{}{}", before, after)
+    } else {
+        format!("{} {}", before.trim_end(), after)
+    }
+    .trim_end()
+    .to_string();
+    Ok(false)
+}
+
+// Moves a link inside a single file. Removing the link's line shifts every line
+// after it up by one, so a destination heading below it has to be looked up one
+// line earlier.
+fn move_link_lines(
+    lines: &mut Vec<String>,
+    original: &Link,
+    heading_line: Option<usize>,
+    dialect: &Dialect,
+) -> Result<usize, LinkorgError> {
+    let removed = delete_link_lines(lines, original, dialect)?;
+    let heading_line = heading_line.map(|line_number| {
+        if removed && line_number > original.line_number {
+            line_number - 1
+        } else {
+            line_number
+        }
+    });
+    add_link_lines(lines, heading_line, &LinkUpdate::from(original), dialect)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+// Returns the index just past the subtree of the heading at `index`, that is
+// the next heading which is not nested under it.
+fn subtree_end(lines: &[String], index: usize, level: usize, dialect: &Dialect) -> usize {
+    (index + 1..lines.len())
+        .find(|&next| {
+            matches!(
+                dialect.classify_at(lines, next),
+                Some(LineType::Heading(heading)) if heading.level <= level
+            )
+        })
+        .unwrap_or(lines.len())
+}
+
+// Headings can only be edited in formats which mark them on their own line.
+fn heading_marker(dialect: &Dialect) -> Result<char, LinkorgError> {
+    dialect
+        .format
+        .heading_marker()
+        .ok_or(LinkorgError::UnsupportedEdit(dialect.format.name()))
+}
+
+fn check_heading_line(
+    line: &str,
+    line_number: usize,
+    dialect: &Dialect,
+) -> Result<(), LinkorgError> {
+    match dialect.classify(line, line_number) {
+        Some(LineType::Heading(_)) => Ok(()),
+        _ => Err(LinkorgError::UnrecognisedLine(line.to_string())),
+    }
+}
+
+fn add_heading_lines(
+    lines: &mut Vec<String>,
+    parent_line: Option<usize>,
+    title: &str,
+    dialect: &Dialect,
+) -> Result<usize, LinkorgError> {
+    heading_marker(dialect)?;
+    let (level, start, end) = match parent_line {
+        None => (1, 0, lines.len()),
+        Some(line_number) => {
+            let parent = heading_at(lines, line_number, dialect)?;
+            let end = subtree_end(lines, line_number - 1, parent.level, dialect);
+            (parent.level + 1, line_number, end)
+        }
+    };
+    let index = (start..end)
+        .rev()
+        .find(|&index| !lines[index].trim().is_empty())
+        .map_or(start, |index| index + 1);
+
+    let new_line = dialect.format.heading_markup(level, title);
+    check_heading_line(&new_line, index + 1, dialect)?;
+    lines.insert(index, new_line);
+    Ok(index + 1)
+}
+
+fn rename_heading_lines(
+    lines: &mut [String],
+    heading_line: usize,
+    title: &str,
+    dialect: &Dialect,
+) -> Result<(), LinkorgError> {
+    let marker = heading_marker(dialect)?;
+    let heading = heading_at(lines, heading_line, dialect)?;
+    // Only the title is replaced, the tags and the rest of the line are kept.
+    let line = &lines[heading_line - 1];
+    let title_from = line.len() - line.trim_start_matches(marker).len();
+    let new_line = match line[title_from..].find(&heading.title) {
+        Some(start) => {
+            let start = title_from + start;
+            let end = start + heading.title.len();
+            format!("{}{}{}", &line[..start], title, &line[end..])
+        }
+        None => dialect.format.heading_markup(heading.level, title),
+    };
+    check_heading_line(&new_line, heading_line, dialect)?;
+    lines[heading_line - 1] = new_line;
+    Ok(())
+}
+
+// Promotes (negative `shift`) or demotes (positive `shift`) a heading together
+// with every heading nested under it.
+fn relevel_heading_lines(
+    lines: &mut [String],
+    heading_line: usize,
+    shift: isize,
+    dialect: &Dialect,
+) -> Result<(), LinkorgError> {
+    let heading = heading_at(lines, heading_line, dialect)?;
+    let start = heading_line - 1;
+    let end = subtree_end(lines, start, heading.level, dialect);
+    if heading.level as isize + shift < 1 {
+        return Err(LinkorgError::TopLevelHeading(heading.title));
+    }
+
+    let marker = heading_marker(dialect)?;
+    for index in start..end {
+        if let Some(LineType::Heading(nested)) = dialect.classify_at(lines, index) {
+            let level = (nested.level as isize + shift) as usize;
+            let rest = lines[index].trim_start_matches(marker).to_string();
+            let markup = dialect.format.heading_markup(level, "");
+            lines[index] = format!("{}{}", markup.trim_end(), rest);
+        }
+    }
+    Ok(())
+}
+
+// Swaps a heading and its subtree with the neighbouring sibling subtree in the
+// given direction.
+fn move_heading_lines(
+    lines: &mut [String],
+    heading_line: usize,
+    direction: Direction,
+    dialect: &Dialect,
+) -> Result<usize, LinkorgError> {
+    heading_marker(dialect)?;
+    let heading = heading_at(lines, heading_line, dialect)?;
+    let start = heading_line - 1;
+    let end = subtree_end(lines, start, heading.level, dialect);
+
+    let sibling = match direction {
+        Direction::Up => {
+            (0..start)
+                .rev()
+                .find_map(|index| match dialect.classify_at(lines, index) {
+                    Some(LineType::Heading(other)) if other.level <= heading.level => {
+                        Some((index, other.level))
+                    }
+                    _ => None,
+                })
+        }
+        Direction::Down => match dialect.classify_at(lines, end) {
+            Some(LineType::Heading(other)) => Some((end, other.level)),
+            _ => None,
+        },
+    };
+
+    match (direction, sibling) {
+        (Direction::Up, Some((index, level))) if level == heading.level => {
+            // The previous sibling's subtree runs right up to this heading
+            lines[index..end].rotate_left(start - index);
+            Ok(index + 1)
+        }
+        (Direction::Down, Some((index, level))) if level == heading.level => {
+            let sibling_end = subtree_end(lines, index, level, dialect);
+            lines[start..sibling_end].rotate_left(end - start);
+            Ok(start + sibling_end - end + 1)
+        }
+        _ => Err(LinkorgError::NoSibling(heading.title)),
+    }
+}
+
+pub fn update_link_in_file(
+    path: PathBuf,
+    original: &Link,
+    update: &LinkUpdate,
+    ratings: &RatingScale,
+) -> Result<FileData, LinkorgError> {
+    let dialect = Dialect::of(&path, ratings)?;
+
+    let update = update.clone().dated(Some(original), NoteDate::today());
+    let mut file = FileLines::read(&path)?;
+    update_link_lines(&mut file.lines, original, &update, &dialect)?;
+    file.write(&path)?;
+
+    read_data_file(path, ratings)
+}
+
+pub fn add_link_to_file(
+    path: PathBuf,
+    heading_line: Option<usize>,
+    link: &LinkUpdate,
+    ratings: &RatingScale,
+) -> Result<FileData, LinkorgError> {
+    let dialect = Dialect::of(&path, ratings)?;
+
+    let link = link.clone().dated(None, NoteDate::today());
+    let mut file = FileLines::read(&path)?;
+    add_link_lines(&mut file.lines, heading_line, &link, &dialect)?;
+    file.write(&path)?;
+
+    read_data_file(path, ratings)
+}
+
+pub fn delete_link_from_file(
+    path: PathBuf,
+    original: &Link,
+    ratings: &RatingScale,
+) -> Result<FileData, LinkorgError> {
+    let dialect = Dialect::of(&path, ratings)?;
+
+    let mut file = FileLines::read(&path)?;
+    delete_link_lines(&mut file.lines, original, &dialect)?;
+    file.write(&path)?;
+
+    read_data_file(path, ratings)
+}
+
+// Moves a link under the heading at `heading_line` of `to`, one of the `notes`,
+// converting it to the link syntax of the destination file. Both files are
+// edited in memory first so nothing is written unless the whole move can go
+// through, and the destination is written before the source so a failed write
+// never loses the link.
+pub fn move_link_between_files(
+    from: PathBuf,
+    original: &Link,
+    to: PathBuf,
+    heading_line: Option<usize>,
+    notes: &[PathBuf],
+    ratings: &RatingScale,
+) -> Result<BTreeMap<PathBuf, FileData>, LinkorgError> {
+    if !notes.contains(&to) {
+        return Err(LinkorgError::NotANote(to));
+    }
+    let from_dialect = Dialect::of(&from, ratings)?;
+    let mut from_file = FileLines::read(&from)?;
+
+    // The same file written two ways has to be edited once, or the second write
+    // would undo the first.
+    let canonical = |path: &Path| {
+        fs::canonicalize(path).map_err(|err| LinkorgError::Io(path.to_path_buf(), err))
+    };
+    if canonical(&from)? == canonical(&to)? {
+        move_link_lines(&mut from_file.lines, original, heading_line, &from_dialect)?;
+        from_file.write(&from)?;
+    } else {
+        let to_dialect = Dialect::of(&to, ratings)?;
+        let mut to_file = FileLines::read(&to)?;
+
+        delete_link_lines(&mut from_file.lines, original, &from_dialect)?;
+        add_link_lines(
+            &mut to_file.lines,
+            heading_line,
+            &LinkUpdate::from(original),
+            &to_dialect,
+        )?;
+        to_file.write(&to)?;
+        from_file.write(&from)?;
+    }
+
+    let mut touched = BTreeMap::new();
+    touched.insert(from.clone(), read_data_file(from, ratings)?);
+    if !touched.contains_key(&to) {
+        touched.insert(to.clone(), read_data_file(to, ratings)?);
+    }
+    Ok(touched)
+}
+
+pub fn add_heading_to_file(
+    path: PathBuf,
+    parent_line: Option<usize>,
+    title: &str,
+    ratings: &RatingScale,
+) -> Result<FileData, LinkorgError> {
+    let dialect = Dialect::of(&path, ratings)?;
+
+    let mut file = FileLines::read(&path)?;
+    add_heading_lines(&mut file.lines, parent_line, title, &dialect)?;
+    file.write(&path)?;
+
+    read_data_file(path, ratings)
+}
+
+pub fn rename_heading_in_file(
+    path: PathBuf,
+    heading_line: usize,
+    title: &str,
+    ratings: &RatingScale,
+) -> Result<FileData, LinkorgError> {
+    let dialect = Dialect::of(&path, ratings)?;
+
+    let mut file = FileLines::read(&path)?;
+    rename_heading_lines(&mut file.lines, heading_line, title, &dialect)?;
+    file.write(&path)?;
+
+    read_data_file(path, ratings)
+}
+
+pub fn relevel_heading_in_file(
+    path: PathBuf,
+    heading_line: usize,
+    shift: isize,
+    ratings: &RatingScale,
+) -> Result<FileData, LinkorgError> {
+    let dialect = Dialect::of(&path, ratings)?;
+
+    let mut file = FileLines::read(&path)?;
+    relevel_heading_lines(&mut file.lines, heading_line, shift, &dialect)?;
+    file.write(&path)?;
+
+    read_data_file(path, ratings)
+}
+
+pub fn move_heading_in_file(
+    path: PathBuf,
+    heading_line: usize,
+    direction: Direction,
+    ratings: &RatingScale,
+) -> Result<FileData, LinkorgError> {
+    let dialect = Dialect::of(&path, ratings)?;
+
+    let mut file = FileLines::read(&path)?;
+    move_heading_lines(&mut file.lines, heading_line, direction, &dialect)?;
+    file.write(&path)?;
+
+    read_data_file(path, ratings)
+}
+
+// A line of a note with the addresses of its links rewritten to their
+// canonical form, shown to the user before it is written.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UrlChange {
+    pub file: PathBuf,
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+// The lines whose link addresses are not canonical yet, rewritten. Only the
+// addresses change, and a table cell which got shorter is padded back to its
+// width so the table stays aligned.
+fn canonical_url_lines(lines: &[String], dialect: &Dialect) -> Vec<(usize, String)> {
+    (0..lines.len())
+        .filter(|&index| matches!(dialect.classify_at(lines, index), Some(LineType::Links(_))))
+        .filter_map(|index| {
+            let line = &lines[index];
+            let mut new_line = line.clone();
+            for span in dialect.find_links(line, index + 1).iter().rev() {
+                let url = &line[span.url.clone()];
+                let canonical = canonical_url(url);
+                if canonical == url {
+                    continue;
+                }
+                if is_table_row(line) {
+                    if let Some(cell_end) = line[span.url.end..].find('|') {
+                        let at = span.url.end + cell_end;
+                        let padding = url
+                            .chars()
+                            .count()
+                            .saturating_sub(canonical.chars().count());
+                        new_line.insert_str(at, &" ".repeat(padding));
+                    }
+                }
+                new_line.replace_range(span.url.clone(), &canonical);
+            }
+            let still_links = matches!(
+                dialect.classify(&new_line, index + 1),
+                Some(LineType::Links(_))
+            );
+            (new_line != *line && still_links).then_some((index, new_line))
+        })
+        .collect()
+}
+
+pub fn preview_canonical_urls(
+    files: &[PathBuf],
+    ratings: &RatingScale,
+) -> Result<Vec<UrlChange>, LinkorgError> {
+    let mut changes = vec![];
+    for file in files {
+        let dialect = Dialect::of(file, ratings)?;
+        let lines = FileLines::read(file)?.lines;
+        changes.extend(
+            canonical_url_lines(&lines, &dialect)
+                .into_iter()
+                .map(|(index, after)| UrlChange {
+                    file: file.clone(),
+                    line_number: index + 1,
+                    before: lines[index].clone(),
+                    after,
+                }),
+        );
+    }
+    Ok(changes)
+}
+
+// Writes the previewed changes to the `notes` they are in. Every file is
+// checked before any of them is written, so nothing changes if a line was edited
+// since the preview or a change is anything but the canonical form of its line.
+pub fn apply_url_changes(
+    changes: &[UrlChange],
+    notes: &[PathBuf],
+    ratings: &RatingScale,
+) -> Result<BTreeMap<PathBuf, FileData>, LinkorgError> {
+    let mut by_file: BTreeMap<&Path, Vec<&UrlChange>> = BTreeMap::new();
+    for change in changes {
+        by_file.entry(&change.file).or_default().push(change);
+    }
+
+    let mut files = vec![];
+    for (path, changes) in by_file {
+        if !notes.iter().any(|note| note == path) {
+            return Err(LinkorgError::NotANote(path.to_path_buf()));
+        }
+        let dialect = Dialect::of(path, ratings)?;
+        let mut file = FileLines::read(path)?;
+        let canonical: BTreeMap<usize, String> = canonical_url_lines(&file.lines, &dialect)
+            .into_iter()
+            .collect();
+        for change in changes {
+            let index = change.line_number.wrapping_sub(1);
+            let line = file
+                .lines
+                .get_mut(index)
+                .ok_or(LinkorgError::MissingLine(change.line_number))?;
+            if *line != change.before {
+                return Err(LinkorgError::LineChanged(change.line_number));
+            }
+            if canonical.get(&index) != Some(&change.after) {
+                return Err(LinkorgError::NotCanonical(change.line_number));
+            }
+            *line = change.after.clone();
+        }
+        files.push((path, file));
+    }
+
+    let mut touched = BTreeMap::new();
+    for (path, file) in files {
+        file.write(path)?;
+        touched.insert(
+            path.to_path_buf(),
+            read_data_file(path.to_path_buf(), ratings)?,
+        );
+    }
+    Ok(touched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::format::{asciidoc::AsciiDoc, markdown::Markdown, org::Org, rst::Rst};
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        static ref RATINGS: RatingScale = RatingScale::default();
+    }
+
+    fn lines(input: &str) -> Vec<String> {
+        input.lines().map(|line| line.to_string()).collect()
+    }
+
+    fn dialect(format: &'static dyn NoteFormat) -> Dialect<'static> {
+        Dialect {
+            format,
+            ratings: &RATINGS,
+        }
+    }
+
+    fn original_link(line: &str, line_number: usize, dialect: &Dialect) -> Link {
+        match dialect.classify(line, line_number) {
+            Some(LineType::Links(mut links)) => links.remove(0),
+            val => panic!("Not a link line {:?}", val),
+        }
+    }
+
+    #[test]
+    fn updating_org_plain_link() {
+        let mut input = lines(
+            "* Heading\n[[Link to test book 1][Table test book 1]] (Good book)      (Must read) -- after 20\n* Other",
+        );
+        let original = original_link(&input[1], 2, &dialect(&Org));
+        let update = LinkUpdate {
+            name: "Renamed book".to_string(),
+            link: "New link".to_string(),
+            read_till: Some(Progress::Page(25)),
+            description: Some("Great book".to_string()),
+            likeability: Some("Must read".to_string()),
+            added: None,
+            finished: None,
+        };
+
+        update_link_lines(&mut input, &original, &update, &dialect(&Org)).unwrap();
+
+        assert_eq!(
+            input,
+            lines("* Heading\n[[New link][Renamed book]]                 (Great book)     (Must read) -- after 25\n* Other")
+        );
+    }
+
+    #[test]
+    fn updating_org_table_link() {
+        let mut input = lines(
+            "| [[Link to test book 2][Table test book 2]] |                  | (Good read) | -- after 2  |",
+        );
+        let original = original_link(&input[0], 1, &dialect(&Org));
+        let update = LinkUpdate {
+            name: "Table test book 2".to_string(),
+            link: "Link to test book 2".to_string(),
+            read_till: Some(Progress::Page(40)),
+            description: Some("Good book".to_string()),
+            likeability: Some("Good read".to_string()),
+            added: None,
+            finished: None,
+        };
+
+        update_link_lines(&mut input, &original, &update, &dialect(&Org)).unwrap();
+
+        assert_eq!(
+            input,
+            lines("| [[Link to test book 2][Table test book 2]] | (Good book)      | (Good read) | -- after 40 |")
+        );
+    }
+
+    #[test]
+    fn updating_table_link_with_custom_ratings() {
+        let ratings = RatingScale(vec!["Loved".to_string(), "Liked".to_string()]);
+        let dialect = Dialect {
+            format: &Org,
+            ratings: &ratings,
+        };
+        let mut input = lines("| [[L][N]] | (Nice) | (Loved) | -- after 2 |");
+        let original = original_link(&input[0], 1, &dialect);
+        let update = LinkUpdate {
+            name: "N".to_string(),
+            link: "L".to_string(),
+            read_till: Some(Progress::Page(5)),
+            description: Some("Nice".to_string()),
+            likeability: Some("Liked".to_string()),
+            added: None,
+            finished: None,
+        };
+
+        update_link_lines(&mut input, &original, &update, &dialect).unwrap();
+
+        assert_eq!(input, lines("| [[L][N]] | (Nice) | (Liked) | -- after 5 |"));
+    }
+
+    #[test]
+    fn updating_md_plain_link() {
+        let mut input = lines(
+            "[Table test book 4](Link to test book 4)                              -- after 9 ",
+        );
+        let original = original_link(&input[0], 1, &dialect(&Markdown));
+        let update = LinkUpdate {
+            name: "Table test book 4".to_string(),
+            link: "Link to test book 4".to_string(),
+            read_till: Some(Progress::Page(10)),
+            description: None,
+            likeability: None,
+            added: None,
+            finished: None,
+        };
+
+        update_link_lines(&mut input, &original, &update, &dialect(&Markdown)).unwrap();
+
+        assert_eq!(
+            input,
+            lines(
+                "[Table test book 4](Link to test book 4)                              -- after 10"
+            )
+        );
+    }
+
+    #[test]
+    fn dating_new_and_finished_links() {
+        let today = NoteDate::parse("2024-03-01").unwrap();
+        let mut input = lines("* Heading\n[[Link][Book]] -- after 20 -- added 2024-01-01");
+        let original = original_link(&input[1], 2, &dialect(&Org));
+        let update = LinkUpdate {
+            read_till: Some(Progress::Done),
+            ..LinkUpdate::from(&original)
+        }
+        .dated(Some(&original), today);
+
+        update_link_lines(&mut input, &original, &update, &dialect(&Org)).unwrap();
+        assert_eq!(
+            input[1],
+            "[[Link][Book]] -- done 2024-03-01 -- added 2024-01-01"
+        );
+        let done = original_link(&input[1], 2, &dialect(&Org));
+        assert_eq!(done.finished, Some(today));
+        // Saving a finished link again keeps the day it was finished on.
+        let again =
+            LinkUpdate::from(&done).dated(Some(&done), NoteDate::parse("2024-04-01").unwrap());
+        assert_eq!(again.finished, Some(today));
+
+        let new = LinkUpdate {
+            name: "New".to_string(),
+            link: "New link".to_string(),
+            read_till: None,
+            description: None,
+            likeability: None,
+            added: None,
+            finished: None,
+        }
+        .dated(None, today);
+        add_link_lines(&mut input, Some(1), &new, &dialect(&Org)).unwrap();
+        assert_eq!(
+            input[2],
+            "[[New link][New]]                 -- added 2024-03-01"
+        );
+    }
+
+    #[test]
+    fn dating_table_link() {
+        let mut input = lines("| [[Link][Book]] | (Must read) | -- after 2 |");
+        let original = original_link(&input[0], 1, &dialect(&Org));
+        let update = LinkUpdate {
+            read_till: Some(Progress::Done),
+            ..LinkUpdate::from(&original)
+        }
+        .dated(Some(&original), NoteDate::parse("2024-03-01").unwrap());
+
+        update_link_lines(&mut input, &original, &update, &dialect(&Org)).unwrap();
+        assert_eq!(
+            input,
+            lines("| [[Link][Book]] | (Must read) | -- done 2024-03-01 |")
+        );
+    }
+
+    #[test]
+    fn canonicalising_link_urls() {
+        let input = lines(
+            "* Books\n[[https://www.amazon.in/Book/dp/B08QHQ7WN5/ref=sr_1?pd_rd_w=Nt2LI&psc=1][Book]]  (Must read) -- after 19\n| [[https://example.com/a?utm_source=x][A]] | -- done |\n[[https://example.com/b][B]]",
+        );
+        assert_eq!(
+            canonical_url_lines(&input, &dialect(&Org)),
+            vec![
+                (
+                    1,
+                    "[[https://www.amazon.in/Book/dp/B08QHQ7WN5][Book]]  (Must read) -- after 19"
+                        .to_string()
+                ),
+                (
+                    2,
+                    "| [[https://example.com/a][A]]              | -- done |".to_string()
+                ),
+            ]
+        );
+
+        let input = lines(
+            "[Book](https://example.com/b/?ref=nav&fbclid=x) and [Other](https://example.com/c)",
+        );
+        assert_eq!(
+            canonical_url_lines(&input, &dialect(&Markdown)),
+            vec![(
+                0,
+                "[Book](https://example.com/b/?ref=nav) and [Other](https://example.com/c)"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn applying_url_changes_to_unchanged_lines_only() {
+        let file = std::env::temp_dir().join("linkorg_applying_url_changes.org");
+        fs::write(
+            &file,
+            "* Books\n[[https://example.com/a?utm_source=x][A]]\n",
+        )
+        .unwrap();
+        let changes = preview_canonical_urls(std::slice::from_ref(&file), &RATINGS).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].after, "[[https://example.com/a][A]]");
+
+        let notes = std::slice::from_ref(&file);
+        let mut stale = changes[0].clone();
+        stale.before = "[[https://example.com/old][A]]".to_string();
+        assert!(matches!(
+            apply_url_changes(&[stale], notes, &RATINGS),
+            Err(LinkorgError::LineChanged(2))
+        ));
+        let mut forged = changes[0].clone();
+        forged.after = "[[https://example.com/other][A]]".to_string();
+        assert!(matches!(
+            apply_url_changes(&[forged], notes, &RATINGS),
+            Err(LinkorgError::NotCanonical(2))
+        ));
+        assert!(matches!(
+            apply_url_changes(&changes, &[], &RATINGS),
+            Err(LinkorgError::NotANote(_))
+        ));
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "* Books\n[[https://example.com/a?utm_source=x][A]]\n"
+        );
+
+        let touched = apply_url_changes(&changes, notes, &RATINGS).unwrap();
+        assert_eq!(
+            touched[&file].heading[0].links[0].link,
+            "https://example.com/a"
+        );
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "* Books\n[[https://example.com/a][A]]\n"
+        );
+    }
+
+    #[test]
+    fn refusing_to_update_changed_line() {
+        let mut input = lines("[[Link to test book 4][Table test book 4]] -- after 9");
+        let mut original = original_link(&input[0], 1, &dialect(&Org));
+        original.read_till = Some(Progress::Page(8));
+        let update = LinkUpdate {
+            name: "Table test book 4".to_string(),
+            link: "Link to test book 4".to_string(),
+            read_till: Some(Progress::Page(10)),
+            description: None,
+            likeability: None,
+            added: None,
+            finished: None,
+        };
+
+        assert!(update_link_lines(&mut input, &original, &update, &dialect(&Org)).is_err());
+        assert_eq!(
+            input,
+            lines("[[Link to test book 4][Table test book 4]] -- after 9")
+        );
+    }
+
+    #[test]
+    fn adding_org_link_to_table() {
+        let mut input = lines(
+            "* Level 1 Table heading\n\
+             |-------------------+------------------+-------------+-------------|\n\
+             | [[Link to test book 1][Table test book 1]] | (Good book)      | (Must read) | -- after 20 |\n\
+             |-------------------+------------------+-------------+-------------|\n\
+             * Next heading",
+        );
+        let link = LinkUpdate {
+            name: "New book".to_string(),
+            link: "New link".to_string(),
+            read_till: Some(Progress::Page(0)),
+            description: None,
+            likeability: Some("Good read".to_string()),
+            added: None,
+            finished: None,
+        };
+
+        let line_number = add_link_lines(&mut input, Some(1), &link, &dialect(&Org)).unwrap();
+
+        assert_eq!(line_number, 4);
+        assert_eq!(
+            input[3],
+            "| [[New link][New book]]                     |                  | (Good read) | -- after 0  |"
+        );
+        assert_eq!(
+            input[4],
+            "|-------------------+------------------+-------------+-------------|"
+        );
+    }
+
+    #[test]
+    fn adding_md_link_to_empty_heading() {
+        let mut input = lines("title: test\n\n# First\nSome notes\n\n# Second");
+        let link = LinkUpdate {
+            name: "New book".to_string(),
+            link: "New link".to_string(),
+            read_till: Some(Progress::Page(3)),
+            description: Some("Nice book".to_string()),
+            likeability: None,
+            added: None,
+            finished: None,
+        };
+
+        let line_number = add_link_lines(&mut input, Some(3), &link, &dialect(&Markdown)).unwrap();
+
+        assert_eq!(line_number, 5);
+        assert_eq!(
+            input,
+            lines("title: test\n\n# First\nSome notes\n[New book](New link) (Nice book) -- after 3\n\n# Second")
+        );
+    }
+
+    #[test]
+    fn adding_link_under_non_heading() {
+        let mut input = lines("* First\n[[Link][Name]] -- after 1");
+        let link = LinkUpdate {
+            name: "New book".to_string(),
+            link: "New link".to_string(),
+            read_till: Some(Progress::Page(3)),
+            description: None,
+            likeability: None,
+            added: None,
+            finished: None,
+        };
+
+        assert!(add_link_lines(&mut input, Some(2), &link, &dialect(&Org)).is_err());
+    }
+
+    #[test]
+    fn deleting_org_link() {
+        let mut input =
+            lines("* First\n[[Link 1][Name 1]] -- after 1\n[[Link 2][Name 2]] -- after 2");
+        let original = original_link(&input[1], 2, &dialect(&Org));
+
+        delete_link_lines(&mut input, &original, &dialect(&Org)).unwrap();
+
+        assert_eq!(input, lines("* First\n[[Link 2][Name 2]] -- after 2"));
+    }
+
+    #[test]
+    fn deleting_link_but_not_its_text() {
+        let mut input = lines(
+            "See [[Link 1][Name 1]] -- after 1 for the details\n  - [[Link 2][Name 2]] (Good read)\n| [[Link 3][Name 3]] | -- done |",
+        );
+        let dialect = dialect(&Org);
+
+        let original = original_link(&input[0], 1, &dialect);
+        assert!(!delete_link_lines(&mut input, &original, &dialect).unwrap());
+        let original = original_link(&input[1], 2, &dialect);
+        assert!(delete_link_lines(&mut input, &original, &dialect).unwrap());
+        let original = original_link(&input[1], 2, &dialect);
+        assert!(delete_link_lines(&mut input, &original, &dialect).unwrap());
+
+        assert_eq!(input, lines("See for the details"));
+    }
+
+    #[test]
+    fn moving_org_link_to_later_heading() {
+        let mut input = lines(
+            "* First\n[[Link 1][Name 1]] -- after 1\n[[Link 2][Name 2]] -- after 2\n* Second\n[[Link 3][Name 3]] -- after 3\n* Third",
+        );
+        let original = original_link(&input[1], 2, &dialect(&Org));
+
+        let line_number = move_link_lines(&mut input, &original, Some(4), &dialect(&Org)).unwrap();
+
+        assert_eq!(line_number, 5);
+        assert_eq!(
+            input,
+            lines("* First\n[[Link 2][Name 2]] -- after 2\n* Second\n[[Link 3][Name 3]] -- after 3\n[[Link 1][Name 1]] -- after 1\n* Third")
+        );
+    }
+
+    #[test]
+    fn moving_md_link_to_file_root() {
+        let mut input = lines("title: test\n# First\n[Name 1](Link 1) (Good book) -- after 1");
+        let original = original_link(&input[2], 3, &dialect(&Markdown));
+
+        let line_number =
+            move_link_lines(&mut input, &original, None, &dialect(&Markdown)).unwrap();
+
+        assert_eq!(line_number, 2);
+        assert_eq!(
+            input,
+            lines("title: test\n[Name 1](Link 1) (Good book) -- after 1\n# First")
+        );
+    }
+
+    #[test]
+    fn moving_link_between_org_and_md_files() {
+        let dir = std::env::temp_dir().join("linkorg_moving_link_between_files");
+        let _ = fs::create_dir_all(&dir);
+        let from = dir.join("from.org");
+        let to = dir.join("to.md");
+        fs::write(
+            &from,
+            "* Books\n[[Link 1][Name 1]] (Must read) -- after 4\n",
+        )
+        .unwrap();
+        fs::write(&to, "# Reading\n").unwrap();
+
+        let original = original_link(
+            "[[Link 1][Name 1]] (Must read) -- after 4",
+            2,
+            &dialect(&Org),
+        );
+        let notes = [from.clone(), to.clone()];
+        assert!(matches!(
+            move_link_between_files(
+                from.clone(),
+                &original,
+                dir.join("other.md"),
+                Some(1),
+                &notes,
+                &RATINGS
+            ),
+            Err(LinkorgError::NotANote(_))
+        ));
+        let touched = move_link_between_files(
+            from.clone(),
+            &original,
+            to.clone(),
+            Some(1),
+            &notes,
+            &RATINGS,
+        )
+        .unwrap();
+
+        assert_eq!(touched.len(), 2);
+        assert_eq!(fs::read_to_string(&from).unwrap(), "* Books\n");
+        assert_eq!(
+            fs::read_to_string(&to).unwrap(),
+            "# Reading\n[Name 1](Link 1) (Must read) -- after 4\n"
+        );
+        assert_eq!(touched[&to].heading[0].links[0].line_number, 2);
+
+        let original = original_link(
+            "[Name 1](Link 1) (Must read) -- after 4",
+            2,
+            &dialect(&Markdown),
+        );
+        let same = dir.join(".").join("to.md");
+        let notes = [same.clone(), to.clone()];
+        move_link_between_files(same, &original, to.clone(), None, &notes, &RATINGS).unwrap();
+        assert_eq!(
+            fs::read_to_string(&to).unwrap(),
+            "[Name 1](Link 1) (Must read) -- after 4\n# Reading\n"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn adding_org_heading_under_parent() {
+        let mut input = lines("* First\n** Nested\n[[Link][Name]] -- after 1\n\n* Second");
+
+        let line_number =
+            add_heading_lines(&mut input, Some(1), "New heading", &dialect(&Org)).unwrap();
+
+        assert_eq!(line_number, 4);
+        assert_eq!(
+            input,
+            lines("* First\n** Nested\n[[Link][Name]] -- after 1\n** New heading\n\n* Second")
+        );
+    }
+
+    #[test]
+    fn renaming_md_heading() {
+        let mut input = lines("# First\n## Nested");
+
+        rename_heading_lines(&mut input, 2, "Renamed", &dialect(&Markdown)).unwrap();
+
+        assert_eq!(input, lines("# First\n## Renamed"));
+    }
+
+    #[test]
+    fn renaming_org_heading_with_tags() {
+        let mut input = lines("* Business   :finance:mgmt:");
+
+        rename_heading_lines(&mut input, 1, "Money", &dialect(&Org)).unwrap();
+
+        assert_eq!(input, lines("* Money   :finance:mgmt:"));
+    }
+
+    #[test]
+    fn demoting_org_heading_with_subtree() {
+        let mut input = lines("* First\n* Second\n** Nested\n[[Link][Name]] -- after 1\n* Third");
+
+        relevel_heading_lines(&mut input, 2, 1, &dialect(&Org)).unwrap();
+
+        assert_eq!(
+            input,
+            lines("* First\n** Second\n*** Nested\n[[Link][Name]] -- after 1\n* Third")
+        );
+        assert!(relevel_heading_lines(&mut input, 1, -1, &dialect(&Org)).is_err());
+    }
+
+    #[test]
+    fn demoting_adoc_heading_with_subtree() {
+        let mut input = lines("== First\n== Second\n=== Nested\nlink:Link[Name] -- after 1");
+
+        relevel_heading_lines(&mut input, 2, 1, &dialect(&AsciiDoc)).unwrap();
+
+        assert_eq!(
+            input,
+            lines("== First\n=== Second\n==== Nested\nlink:Link[Name] -- after 1")
+        );
+        assert!(relevel_heading_lines(&mut input, 1, -1, &dialect(&AsciiDoc)).is_err());
+    }
+
+    #[test]
+    fn editing_rst_links_but_not_headings() {
+        let mut input = lines(
+            "Heading\n=======\n\n+------------------+-------------+\n| `Name <Link>`_   | -- after 1  |\n+------------------+-------------+",
+        );
+        let original = original_link(&input[4], 5, &dialect(&Rst));
+        let update = LinkUpdate {
+            read_till: Some(Progress::Page(2)),
+            ..LinkUpdate::from(&original)
+        };
+
+        update_link_lines(&mut input, &original, &update, &dialect(&Rst)).unwrap();
+
+        assert_eq!(input[4], "| `Name <Link>`_   | -- after 2  |");
+        assert!(matches!(
+            rename_heading_lines(&mut input, 1, "Renamed", &dialect(&Rst)),
+            Err(LinkorgError::UnsupportedEdit(_))
+        ));
+    }
+
+    #[test]
+    fn moving_org_heading_up_and_down() {
+        let mut input = lines(
+            "* First\n[[Link 1][Name 1]] -- after 1\n* Second\n** Nested\n[[Link 2][Name 2]] -- after 2\n* Third",
+        );
+
+        let line_number = move_heading_lines(&mut input, 3, Direction::Up, &dialect(&Org)).unwrap();
+
+        assert_eq!(line_number, 1);
+        assert_eq!(
+            input,
+            lines("* Second\n** Nested\n[[Link 2][Name 2]] -- after 2\n* First\n[[Link 1][Name 1]] -- after 1\n* Third")
+        );
+
+        let line_number =
+            move_heading_lines(&mut input, 1, Direction::Down, &dialect(&Org)).unwrap();
+
+        assert_eq!(line_number, 3);
+        assert_eq!(
+            input,
+            lines("* First\n[[Link 1][Name 1]] -- after 1\n* Second\n** Nested\n[[Link 2][Name 2]] -- after 2\n* Third")
+        );
+        assert!(move_heading_lines(&mut input, 4, Direction::Up, &dialect(&Org)).is_err());
+    }
+
+    #[test]
+    fn updating_second_link_on_line() {
+        let mut input = lines("- [[Link 1][Name 1]] (Good book) and [[Link 2][Name 2]] -- after 2");
+        let original = match dialect(&Org).classify(&input[0], 1) {
+            Some(LineType::Links(mut links)) => links.remove(1),
+            val => panic!("Not a link line {:?}", val),
+        };
+        let update = LinkUpdate {
+            name: "Name 2".to_string(),
+            link: "Link 2".to_string(),
+            read_till: None,
+            description: None,
+            likeability: Some("Must read".to_string()),
+            added: None,
+            finished: None,
+        };
+
+        update_link_lines(&mut input, &original, &update, &dialect(&Org)).unwrap();
+
+        assert_eq!(
+            input,
+            lines("- [[Link 1][Name 1]] (Good book) and [[Link 2][Name 2]] (Must read)")
+        );
+    }
+
+    #[test]
+    fn deleting_first_link_on_line() {
+        let mut input = lines("- [[Link 1][Name 1]] (Good book) [[Link 2][Name 2]] -- after 2");
+        let original = original_link(&input[0], 1, &dialect(&Org));
+
+        let removed = delete_link_lines(&mut input, &original, &dialect(&Org)).unwrap();
+
+        assert!(!removed);
+        assert_eq!(input, lines("- [[Link 2][Name 2]] -- after 2"));
+    }
+
+    #[test]
+    fn adding_org_link_after_list_item() {
+        let mut input = lines("* Books\n  - [[Link 1][Name 1]]");
+        let link = LinkUpdate {
+            name: "Name 2".to_string(),
+            link: "Link 2".to_string(),
+            read_till: None,
+            description: None,
+            likeability: None,
+            added: None,
+            finished: None,
+        };
+
+        add_link_lines(&mut input, Some(1), &link, &dialect(&Org)).unwrap();
+
+        assert_eq!(
+            input,
+            lines("* Books\n  - [[Link 1][Name 1]]\n  - [[Link 2][Name 2]]")
+        );
+    }
+}
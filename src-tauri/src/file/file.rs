@@ -1,25 +1,445 @@
-use std::path::PathBuf;
-use walkdir::WalkDir;
-
-pub fn find_data_files(path: PathBuf) -> Vec<PathBuf> {
-    let files = WalkDir::new(path)
-        .into_iter()
-        .filter_map(|entry| {
-            entry.ok().and_then(|e| {
-                let path = e.path();
-                if path.is_file() {
-                    path.extension().and_then(|ext| {
-                        if ext == "org" || ext == "md" {
-                            Some(path.to_path_buf())
-                        } else {
-                            None
-                        }
-                    })
-                } else {
-                    None
-                }
-            })
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{gitignore::GitignoreBuilder, Match, WalkBuilder};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::{config::config::Source, error::LinkorgError};
+
+use super::format::format_of;
+
+// Folders and files which are never notes: version control, dependencies,
+// archives and Emacs backups and lock files.
+const DEFAULT_EXCLUDES: [&str; 7] = [
+    ".git",
+    "node_modules",
+    "archive",
+    "Archive",
+    "*~",
+    "#*#",
+    ".#*",
+];
+
+lazy_static! {
+    static ref DEFAULT_EXCLUDE_SET: GlobSet = glob_set(
+        &DEFAULT_EXCLUDES
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect::<Vec<_>>()
+    )
+    .unwrap();
+}
+
+// The notes found in one source, or why they couldn't be listed.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct SourceFiles {
+    pub name: String,
+    pub path: PathBuf,
+    pub files: Vec<PathBuf>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, LinkorgError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|err| LinkorgError::InvalidGlob(pattern.to_string(), err.to_string()))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|err| LinkorgError::InvalidGlob(patterns.join(", "), err.to_string()))
+}
+
+fn is_note(path: &Path) -> bool {
+    format_of(path).is_some()
+}
+
+fn is_excluded_by_default(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| DEFAULT_EXCLUDE_SET.is_match(name))
+}
+
+// The ignore files read while walking a source, the ones closer to the note
+// taking precedence over the ones further up.
+const IGNORE_FILES: [&str; 3] = [".linkorgignore", ".gitignore", ".git/info/exclude"];
+
+// Whether the ignore files in the folders above `path` leave it out, the way
+// the walk in find_data_files reads them.
+fn is_ignored(path: &Path) -> bool {
+    for dir in path.ancestors().skip(1) {
+        for name in IGNORE_FILES {
+            let file = dir.join(name);
+            if !file.is_file() {
+                continue;
+            }
+            let mut builder = GitignoreBuilder::new(dir);
+            if builder.add(&file).is_some() {
+                continue;
+            }
+            let Ok(ignore) = builder.build() else {
+                continue;
+            };
+            match ignore.matched_path_or_any_parents(path, false) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+    }
+    false
+}
+
+// Whether `path` is one of the notes find_data_files lists for `source`,
+// without walking the whole source. The path doesn't have to exist anymore,
+// like the one of a removed note.
+pub(crate) fn is_source_note(source: &Source, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(&source.path) else {
+        return false;
+    };
+    let depth = relative.components().count();
+    if depth == 0
+        || !is_note(path)
+        || source
+            .max_depth
+            .is_some_and(|max_depth| depth > max_depth + 1)
+    {
+        return false;
+    }
+    let (Ok(include), Ok(exclude)) = (glob_set(&source.include), glob_set(&source.exclude)) else {
+        return false;
+    };
+    // Every folder on the way counts, as the walk doesn't go into excluded ones.
+    let excluded = relative
+        .ancestors()
+        .filter(|entry| !entry.as_os_str().is_empty())
+        .any(|entry| {
+            let path = source.path.join(entry);
+            (source.default_excludes && is_excluded_by_default(&path))
+                || exclude.is_match(entry)
+                || (!source.follow_links
+                    && fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink()))
+        });
+    !excluded
+        && (source.include.is_empty() || include.is_match(relative))
+        && !(source.ignore_files && is_ignored(path))
+}
+
+pub fn find_data_files(source: &Source) -> Result<Vec<PathBuf>, LinkorgError> {
+    // Only a notes directory which can't be read at all is an error,
+    // unreadable entries inside of it are skipped.
+    fs::read_dir(&source.path).map_err(|err| LinkorgError::Io(source.path.to_path_buf(), err))?;
+
+    let include = glob_set(&source.include)?;
+    let exclude = glob_set(&source.exclude)?;
+    let root = source.path.clone();
+    let default_excludes = source.default_excludes;
+
+    let mut walker = WalkBuilder::new(&source.path);
+    walker
+        .max_depth(source.max_depth.map(|max_depth| max_depth + 1))
+        .follow_links(source.follow_links)
+        .hidden(false)
+        .ignore(false)
+        .git_global(false)
+        .git_ignore(source.ignore_files)
+        .git_exclude(source.ignore_files)
+        .parents(source.ignore_files)
+        .require_git(false);
+    if source.ignore_files {
+        walker.add_custom_ignore_filename(".linkorgignore");
+    }
+    // Excluded folders are not walked into at all.
+    walker.filter_entry(move |entry| {
+        let path = entry.path();
+        entry.depth() == 0
+            || !((default_excludes && is_excluded_by_default(path))
+                || exclude.is_match(path.strip_prefix(&root).unwrap_or(path)))
+    });
+
+    let mut files = vec![];
+    for entry in walker.build().flatten() {
+        let path = entry.path();
+        // Without follow_links symlinks are neither files nor folders.
+        if entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+            && is_note(path)
+            && (source.include.is_empty()
+                || include.is_match(path.strip_prefix(&source.path).unwrap_or(path)))
+        {
+            files.push(path.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+// Creates an empty note called `name` at the top of `source`. A name without
+// the extension of a supported format gets the `default_format` of the source.
+pub fn create_note(source: &Source, name: &str) -> Result<PathBuf, LinkorgError> {
+    let name = name.trim();
+    let file_name = Path::new(name)
+        .file_name()
+        .filter(|file_name| *file_name == name)
+        .ok_or_else(|| LinkorgError::InvalidNoteName(name.to_string()))?;
+    let mut path = source.path.join(file_name);
+    if !is_note(&path) {
+        path = source
+            .path
+            .join(format!("{}.{}", name, source.default_format));
+    }
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|err| match err.kind() {
+            ErrorKind::AlreadyExists => LinkorgError::NoteExists(path.clone()),
+            _ => LinkorgError::Io(path.clone(), err),
+        })?;
+    Ok(path)
+}
+
+// The notes of every source. A source which can't be listed, like a missing
+// folder, has no notes and its error instead, the other ones are still listed.
+pub fn find_source_files(sources: &[Source]) -> Vec<SourceFiles> {
+    sources
+        .iter()
+        .map(|source| {
+            let (files, error) = match find_data_files(source) {
+                Ok(files) => (files, None),
+                Err(err) => (vec![], Some(err.to_string())),
+            };
+            SourceFiles {
+                name: source.name.clone(),
+                path: source.path.clone(),
+                files,
+                error,
+            }
         })
-        .collect();
-    files
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn test_source(name: &str, files: &[&str]) -> Source {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        Source::new(name, dir)
+    }
+
+    fn found(source: &Source) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = find_data_files(source)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(&source.path).unwrap().to_path_buf())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn finding_files_with_globs() {
+        let mut source = test_source(
+            "linkorg_finding_files_with_globs",
+            &[
+                "books.org",
+                "videos.md",
+                "image.png",
+                "archive/old.org",
+                "reading/papers.org",
+                "reading/draft.org",
+            ],
+        );
+        source.exclude = vec!["archive".to_string(), "*draft*".to_string()];
+        assert_eq!(
+            found(&source),
+            vec![
+                PathBuf::from("books.org"),
+                PathBuf::from("reading/papers.org"),
+                PathBuf::from("videos.md"),
+            ]
+        );
+
+        source.include = vec!["*.org".to_string()];
+        assert_eq!(
+            found(&source),
+            vec![
+                PathBuf::from("books.org"),
+                PathBuf::from("reading/papers.org"),
+            ]
+        );
+    }
+
+    #[test]
+    fn finding_files_up_to_depth() {
+        let mut source = test_source(
+            "linkorg_finding_files_up_to_depth",
+            &["top.org", "one/one.org", "one/two/two.org"],
+        );
+        source.max_depth = Some(0);
+        assert_eq!(found(&source), vec![PathBuf::from("top.org")]);
+        source.max_depth = Some(1);
+        assert_eq!(
+            found(&source),
+            vec![PathBuf::from("one/one.org"), PathBuf::from("top.org")]
+        );
+    }
+
+    #[test]
+    fn finding_files_with_invalid_glob() {
+        let mut source = test_source("linkorg_finding_files_with_invalid_glob", &[]);
+        fs::create_dir_all(&source.path).unwrap();
+        source.exclude = vec!["[".to_string()];
+        assert!(matches!(
+            find_data_files(&source),
+            Err(LinkorgError::InvalidGlob(_, _))
+        ));
+    }
+
+    #[test]
+    fn finding_files_without_ignored() {
+        let mut source = test_source(
+            "linkorg_finding_files_without_ignored",
+            &[
+                "books.org",
+                ".#books.org",
+                "#books.org#",
+                "books.org~",
+                ".git/notes.org",
+                "node_modules/package/README.md",
+                "archive/old.org",
+                "drafts/draft.org",
+                "private/diary.org",
+                "reading/private.md",
+                "reading/papers.md",
+            ],
+        );
+        fs::write(source.path.join(".gitignore"), "drafts/\n").unwrap();
+        fs::write(source.path.join("reading/.linkorgignore"), "private.md\n").unwrap();
+        fs::write(source.path.join(".linkorgignore"), "/private\n").unwrap();
+        assert_eq!(
+            found(&source),
+            vec![
+                PathBuf::from("books.org"),
+                PathBuf::from("reading/papers.md"),
+            ]
+        );
+
+        source.ignore_files = false;
+        source.default_excludes = false;
+        assert_eq!(found(&source).len(), 9);
+    }
+
+    #[test]
+    fn finding_files_of_healthy_sources() {
+        let books = test_source("linkorg_finding_files_of_healthy_sources", &["books.org"]);
+        let missing = Source::new("missing", books.path.join("missing"));
+        let found = find_source_files(&[missing, books.clone()]);
+        assert!(found[0].files.is_empty());
+        assert!(found[0].error.is_some());
+        assert_eq!(found[1].files, vec![books.path.join("books.org")]);
+        assert_eq!(found[1].error, None);
+    }
+
+    #[test]
+    fn recognising_source_notes() {
+        let mut source = test_source(
+            "linkorg_recognising_source_notes",
+            &[
+                "books.org",
+                "books.org~",
+                ".git/notes.org",
+                "archive/old.org",
+                "drafts/draft.org",
+                "private/diary.org",
+                "reading/private.md",
+                "reading/papers.md",
+                "reading/deep/down.org",
+                "reading/skipped.org",
+            ],
+        );
+        fs::write(source.path.join(".gitignore"), "drafts/\n").unwrap();
+        fs::write(source.path.join("reading/.linkorgignore"), "private.md\n").unwrap();
+        fs::write(source.path.join(".linkorgignore"), "/private\n").unwrap();
+        source.max_depth = Some(1);
+        source.exclude = vec!["*skipped*".to_string()];
+
+        let found = found(&source);
+        for file in [
+            "books.org",
+            "books.org~",
+            ".git/notes.org",
+            "archive/old.org",
+            "drafts/draft.org",
+            "private/diary.org",
+            "reading/private.md",
+            "reading/papers.md",
+            "reading/deep/down.org",
+            "reading/skipped.org",
+            "removed.org",
+        ] {
+            let is_found = found.contains(&PathBuf::from(file)) || file == "removed.org";
+            assert_eq!(
+                is_source_note(&source, &source.path.join(file)),
+                is_found,
+                "{}",
+                file
+            );
+        }
+        assert!(!is_source_note(&source, Path::new("/elsewhere/books.org")));
+    }
+
+    #[test]
+    fn creating_notes_in_default_format() {
+        let mut source = test_source("linkorg_creating_notes_in_default_format", &["books.org"]);
+        source.default_format = "md".to_string();
+
+        let note = create_note(&source, "papers").unwrap();
+        assert_eq!(note, source.path.join("papers.md"));
+        assert!(note.is_file());
+        assert_eq!(
+            create_note(&source, "videos.org").unwrap(),
+            source.path.join("videos.org")
+        );
+        assert!(matches!(
+            create_note(&source, "books.org"),
+            Err(LinkorgError::NoteExists(_))
+        ));
+        assert!(matches!(
+            create_note(&source, "../outside"),
+            Err(LinkorgError::InvalidNoteName(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finding_files_through_symlinks() {
+        let mut source = test_source("linkorg_finding_files_through_symlinks", &["books.org"]);
+        let outside = test_source(
+            "linkorg_finding_files_through_symlinks_outside",
+            &["papers.org"],
+        );
+        std::os::unix::fs::symlink(&outside.path, source.path.join("outside")).unwrap();
+        assert_eq!(found(&source), vec![PathBuf::from("books.org")]);
+
+        source.follow_links = true;
+        assert_eq!(
+            found(&source),
+            vec![
+                PathBuf::from("books.org"),
+                PathBuf::from("outside/papers.org"),
+            ]
+        );
+    }
 }
@@ -0,0 +1,68 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::config::RatingScale;
+use crate::file::parse::{classify_line, find_links, LineType, LinkSpan, RegexFileCollections};
+
+use super::NoteFormat;
+
+lazy_static! {
+    // Links are written as `link:url[name]`, or just `url[name]` for web addresses.
+    // The document title is `= Title` and sections start at `==`, so the level
+    // of a heading is one less than its number of `=`. Header attributes hold
+    // the metadata.
+    pub(crate) static ref RE_ADOC: RegexFileCollections = RegexFileCollections {
+        links: Regex::new(
+            r"(?:link:([^\s\[\]]+)|\b((?:https?|ftp|file|irc)://[^\s\[\]]+))\[([^\[\]]*)\]"
+        )
+        .unwrap(),
+        link_groups: &[1, 2],
+        name_group: 3,
+        heading: Some(Regex::new(r"^=(?P<level>=+) +(?P<title>\w.*)").unwrap()),
+        title: Some(Regex::new(r"^= +(\S.*)").unwrap()),
+        date: Some(Regex::new(r"^:(?i)(?:revdate|date): *(.*)").unwrap()),
+        tags: Some(Regex::new(r"^:(?i)(?:keywords|tags): *(.*)").unwrap()),
+        description: Some(Regex::new(r"^:(?i)description: *(.*)").unwrap()),
+        keyword: Some(Regex::new(r"^:([\w-]+): *(.*)").unwrap()),
+        property: None,
+        drawer: None,
+        planning: None,
+    };
+}
+
+pub(crate) struct AsciiDoc;
+
+impl NoteFormat for AsciiDoc {
+    fn name(&self) -> &'static str {
+        "AsciiDoc"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["adoc", "asciidoc"]
+    }
+
+    fn classify_line(
+        &self,
+        line: &str,
+        line_number: usize,
+        ratings: &RatingScale,
+    ) -> Option<LineType> {
+        classify_line(line, line_number, &RE_ADOC, ratings)
+    }
+
+    fn find_links(&self, line: &str, line_number: usize, ratings: &RatingScale) -> Vec<LinkSpan> {
+        find_links(line, line_number, &RE_ADOC, ratings)
+    }
+
+    fn link_markup(&self, link: &str, name: &str) -> String {
+        format!("link:{}[{}]", link, name)
+    }
+
+    fn heading_marker(&self) -> Option<char> {
+        Some('=')
+    }
+
+    fn heading_markup(&self, level: usize, title: &str) -> String {
+        format!("{} {}", "=".repeat(level + 1), title)
+    }
+}
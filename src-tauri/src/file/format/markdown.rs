@@ -0,0 +1,242 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::config::config::RatingScale;
+use crate::file::date::NoteDate;
+use crate::file::parse::{
+    build_file_data, classify_line, find_links, inherit_tags, FileData, FileMetaData, LineType,
+    LinkSpan, RegexFileCollections,
+};
+
+use super::NoteFormat;
+
+lazy_static! {
+    pub(crate) static ref RE_MD: RegexFileCollections = RegexFileCollections {
+        links: Regex::new(r"\[([^\[\]]*)\]\(([^()]*)\)").unwrap(),
+        link_groups: &[2],
+        name_group: 1,
+        heading: Some(Regex::new(r"^(?P<level>#+) *(?P<title>\w.*)").unwrap()),
+        title: None,
+        date: None,
+        tags: None,
+        description: None,
+        keyword: None,
+        property: None,
+        drawer: None,
+        planning: None,
+    };
+}
+
+// The metadata of a note is in a front matter block on its very first lines,
+// between `---` lines for YAML or `+++` lines for TOML.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrontMatter {
+    Yaml,
+    Toml,
+}
+
+// Returns the kind of the front matter block and the index of its closing line.
+fn front_matter(lines: &[String]) -> Option<(FrontMatter, usize)> {
+    let kind = match lines.first()?.trim_end() {
+        "---" => FrontMatter::Yaml,
+        "+++" => FrontMatter::Toml,
+        _ => return None,
+    };
+    let end = lines[1..].iter().position(|line| match kind {
+        FrontMatter::Yaml => matches!(line.trim_end(), "---" | "..."),
+        FrontMatter::Toml => line.trim_end() == "+++",
+    })?;
+    Some((kind, end + 1))
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::Datetime(date) => Value::String(date.to_string()),
+        toml::Value::Array(values) => Value::Array(values.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+        value => serde_json::to_value(value).unwrap_or(Value::Null),
+    }
+}
+
+// The keys of a front matter block, or why it isn't valid.
+fn read_front_matter(kind: FrontMatter, text: &str) -> Result<Map<String, Value>, String> {
+    match kind {
+        FrontMatter::Yaml => serde_yaml::from_str::<serde_yaml::Mapping>(text)
+            .map_err(|err| err.to_string())
+            .map(|mapping| {
+                mapping
+                    .into_iter()
+                    .filter_map(|(key, value)| {
+                        Some((key.as_str()?.to_string(), serde_json::to_value(value).ok()?))
+                    })
+                    .collect()
+            }),
+        FrontMatter::Toml => toml::from_str::<toml::value::Table>(text)
+            .map_err(|err| err.to_string())
+            .map(|table| {
+                table
+                    .into_iter()
+                    .map(|(key, value)| (key, toml_to_json(value)))
+                    .collect()
+            }),
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+// Tags are either a list or a single comma separated string.
+fn tags(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Array(values) => values.iter().map(text).collect(),
+        Value::String(tags) => Some(
+            tags.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn apply_front_matter(meta_data: &mut FileMetaData, fields: Map<String, Value>) {
+    for (key, value) in fields {
+        let known = match key.to_lowercase().as_str() {
+            "title" => text(&value).map(|title| meta_data.file_title = title),
+            "description" => {
+                text(&value).map(|description| meta_data.file_description = description)
+            }
+            "date" => text(&value)
+                .and_then(|date| NoteDate::parse(&date))
+                .map(|date| meta_data.file_date = Some(date)),
+            "tags" => tags(&value).map(|tags| meta_data.file_tags = tags),
+            _ => None,
+        };
+        if known.is_none() {
+            meta_data.file_extra.insert(key, value);
+        }
+    }
+}
+
+pub(crate) struct Markdown;
+
+impl NoteFormat for Markdown {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["md"]
+    }
+
+    fn classify_line(
+        &self,
+        line: &str,
+        line_number: usize,
+        ratings: &RatingScale,
+    ) -> Option<LineType> {
+        classify_line(line, line_number, &RE_MD, ratings)
+    }
+
+    fn find_links(&self, line: &str, line_number: usize, ratings: &RatingScale) -> Vec<LinkSpan> {
+        find_links(line, line_number, &RE_MD, ratings)
+    }
+
+    fn body_start(&self, lines: &[String]) -> usize {
+        front_matter(lines).map_or(0, |(_, end)| end + 1)
+    }
+
+    fn parse(&self, file_name: String, lines: &[String], ratings: &RatingScale) -> FileData {
+        let mut data = build_file_data(self, file_name, lines, ratings);
+        if let Some((kind, end)) = front_matter(lines) {
+            // Front matter which isn't valid is left out as a whole.
+            match read_front_matter(kind, &lines[1..end].join("\n")) {
+                Ok(fields) => {
+                    apply_front_matter(&mut data.file_meta_data, fields);
+                    inherit_tags(&mut data);
+                }
+                Err(err) => {
+                    data.file_meta_data.file_error =
+                        Some(format!("Front matter can't be parsed : {}", err))
+                }
+            }
+        }
+        data
+    }
+
+    fn link_markup(&self, link: &str, name: &str) -> String {
+        format!("[{}]({})", name, link)
+    }
+
+    fn heading_marker(&self) -> Option<char> {
+        Some('#')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> FileData {
+        let lines: Vec<String> = input.lines().map(|line| line.to_string()).collect();
+        Markdown.parse("test.md".to_string(), &lines, &RatingScale::default())
+    }
+
+    #[test]
+    fn reading_yaml_front_matter() {
+        let data = parse(
+            "---\ntitle: Reading list\ndate: 2023-07-10\ntags: [books, rust]\nauthor: Someone\n# not a heading\n---\n# Books\n[Name](Link)",
+        );
+        assert_eq!(data.file_meta_data.file_title, "Reading list");
+        assert_eq!(data.file_meta_data.file_date, NoteDate::parse("2023-07-10"));
+        assert_eq!(data.file_meta_data.file_tags, vec!["books", "rust"]);
+        assert_eq!(
+            data.file_meta_data.file_extra.get("author"),
+            Some(&Value::String("Someone".to_string()))
+        );
+        assert_eq!(data.heading.len(), 1);
+        assert_eq!(data.heading[0].line_number, 8);
+    }
+
+    #[test]
+    fn reading_toml_front_matter() {
+        let data = parse(
+            "+++\ntitle = \"Reading list\"\ndate = 2023-07-10\ntags = \"books, rust\"\ndraft = true\n+++\n# Books",
+        );
+        assert_eq!(data.file_meta_data.file_title, "Reading list");
+        assert_eq!(data.file_meta_data.file_date, NoteDate::parse("2023-07-10"));
+        assert_eq!(data.file_meta_data.file_tags, vec!["books", "rust"]);
+        assert_eq!(
+            data.file_meta_data.file_extra.get("draft"),
+            Some(&Value::Bool(true))
+        );
+        assert_eq!(data.heading[0].line_number, 7);
+    }
+
+    #[test]
+    fn ignoring_metadata_in_the_body() {
+        let data = parse("# Books\nSubtitle: foo\ntitle: bar\n---\ntitle: baz\n---");
+        assert_eq!(data.file_meta_data, FileMetaData::default());
+
+        let data = parse("---\ntitle: [unclosed\n---\n# Books");
+        assert_eq!(data.file_meta_data.file_title, "No title");
+        assert!(data
+            .file_meta_data
+            .file_error
+            .is_some_and(|err| err.starts_with("Front matter can't be parsed")));
+        assert_eq!(data.heading[0].line_number, 4);
+    }
+}
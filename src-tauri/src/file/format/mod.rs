@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use crate::config::config::RatingScale;
+
+use super::parse::{build_file_data, FileData, LineType, LinkSpan};
+
+pub mod asciidoc;
+pub mod markdown;
+pub mod org;
+pub mod rst;
+
+use asciidoc::AsciiDoc;
+use markdown::Markdown;
+use org::Org;
+use rst::Rst;
+
+// Every supported note format. A new format only has to be added here to be
+// found, parsed and edited.
+static FORMATS: [&dyn NoteFormat; 4] = [&Org, &Markdown, &AsciiDoc, &Rst];
+
+// A markup language notes can be written in.
+pub(crate) trait NoteFormat: Sync {
+    fn name(&self) -> &'static str;
+
+    fn extensions(&self) -> &'static [&'static str];
+
+    fn detect(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions().contains(&ext))
+    }
+
+    // What a single line holds, on its own.
+    fn classify_line(
+        &self,
+        line: &str,
+        line_number: usize,
+        ratings: &RatingScale,
+    ) -> Option<LineType>;
+
+    // What a line holds when the line after it is known, for formats whose
+    // headings span two lines.
+    fn classify_with_next(
+        &self,
+        line: &str,
+        _next: Option<&str>,
+        line_number: usize,
+        ratings: &RatingScale,
+    ) -> Option<LineType> {
+        self.classify_line(line, line_number, ratings)
+    }
+
+    fn find_links(&self, line: &str, line_number: usize, ratings: &RatingScale) -> Vec<LinkSpan>;
+
+    // The index of the first line after the metadata block at the top of a
+    // note, for formats which have one.
+    fn body_start(&self, _lines: &[String]) -> usize {
+        0
+    }
+
+    fn parse(&self, file_name: String, lines: &[String], ratings: &RatingScale) -> FileData {
+        build_file_data(self, file_name, lines, ratings)
+    }
+
+    fn link_markup(&self, link: &str, name: &str) -> String;
+
+    // The character repeated in front of a heading to give its level, for
+    // formats which mark headings that way.
+    fn heading_marker(&self) -> Option<char>;
+
+    // Formats without a heading marker have to write their own headings.
+    fn heading_markup(&self, level: usize, title: &str) -> String {
+        match self.heading_marker() {
+            Some(marker) => format!("{} {}", marker.to_string().repeat(level), title),
+            None => title.to_string(),
+        }
+    }
+}
+
+pub(crate) fn format_of(path: &Path) -> Option<&'static dyn NoteFormat> {
+    FORMATS.iter().copied().find(|format| format.detect(path))
+}
+
+// The extensions notes can be written with, in the order of `FORMATS`.
+pub(crate) fn note_extensions() -> Vec<&'static str> {
+    FORMATS
+        .iter()
+        .flat_map(|format| format.extensions().iter().copied())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detecting_formats() {
+        let name = |path: &str| format_of(Path::new(path)).map(|format| format.name());
+        assert_eq!(name("/notes/books.org"), Some("Org"));
+        assert_eq!(name("/notes/videos.md"), Some("Markdown"));
+        assert_eq!(name("/notes/papers.adoc"), Some("AsciiDoc"));
+        assert_eq!(name("/notes/talks.rst"), Some("reStructuredText"));
+        assert_eq!(name("/notes/books.org~"), None);
+        assert_eq!(name("/notes/image.png"), None);
+        assert_eq!(name("/notes/README"), None);
+    }
+}
@@ -0,0 +1,67 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::config::RatingScale;
+use crate::file::parse::{classify_line, find_links, LineType, LinkSpan, RegexFileCollections};
+
+use super::NoteFormat;
+
+lazy_static! {
+    pub(crate) static ref RE_ORG: RegexFileCollections = RegexFileCollections {
+        links: Regex::new(r"\[\[([^\[\]]+)\](?:\[([^\[\]]*)\])?\]").unwrap(),
+        link_groups: &[1],
+        name_group: 2,
+        heading: Some(
+            Regex::new(
+                r"^(?P<level>\*+) *(?:(?P<state>TODO|DONE) +)?(?:\[#(?P<priority>[A-Z0-9])\] +)?(?P<title>\w.*?)(?:\s+:(?P<tags>(?:[\w@#%]+:)+))?\s*$"
+            )
+            .unwrap()
+        ),
+        title: Some(Regex::new(r"#\+(?i)title: *(.*)").unwrap()),
+        date: Some(Regex::new(r"#\+(?i)date: *(.*)").unwrap()),
+        tags: Some(Regex::new(r"#\+(?i)filetags: *:(.*):*").unwrap()),
+        description: Some(Regex::new(r"#\+(?i)description: *(.*)").unwrap()),
+        keyword: Some(Regex::new(r"^#\+(\w[\w-]*): *(.*)").unwrap()),
+        property: Some(Regex::new(r"^\s*:([^:\s]+):\s+(\S.*?)\s*$").unwrap()),
+        drawer: Some(Regex::new(r"^\s*:([\w-]+):\s*$").unwrap()),
+        planning: Some(
+            Regex::new(
+                r"\b(SCHEDULED|DEADLINE|CLOSED):\s*([<\[][^<>\[\]]*[>\]](?:--[<\[][^<>\[\]]*[>\]])?)"
+            )
+            .unwrap()
+        ),
+    };
+}
+
+pub(crate) struct Org;
+
+impl NoteFormat for Org {
+    fn name(&self) -> &'static str {
+        "Org"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["org"]
+    }
+
+    fn classify_line(
+        &self,
+        line: &str,
+        line_number: usize,
+        ratings: &RatingScale,
+    ) -> Option<LineType> {
+        classify_line(line, line_number, &RE_ORG, ratings)
+    }
+
+    fn find_links(&self, line: &str, line_number: usize, ratings: &RatingScale) -> Vec<LinkSpan> {
+        find_links(line, line_number, &RE_ORG, ratings)
+    }
+
+    fn link_markup(&self, link: &str, name: &str) -> String {
+        format!("[[{}][{}]]", link, name)
+    }
+
+    fn heading_marker(&self) -> Option<char> {
+        Some('*')
+    }
+}
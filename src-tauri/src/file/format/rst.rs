@@ -0,0 +1,119 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+use crate::config::config::RatingScale;
+use crate::file::parse::{
+    classify_line, find_links, Heading, LineType, LinkSpan, RegexFileCollections,
+};
+
+use super::NoteFormat;
+
+lazy_static! {
+    // Links are written as `name <url>`_ and the metadata is in the fields of
+    // the docinfo or a `.. meta::` block. Headings are underlined, so they are
+    // found by `classify_with_next` instead of a regular expression.
+    pub(crate) static ref RE_RST: RegexFileCollections = RegexFileCollections {
+        links: Regex::new(r"`([^`<>]*?)\s*<([^<>`]+)>`__?").unwrap(),
+        link_groups: &[2],
+        name_group: 1,
+        heading: None,
+        title: Some(Regex::new(r"^\.\. +(?i)title:: *(.*)").unwrap()),
+        date: Some(Regex::new(r"^:(?i)date: *(.*)").unwrap()),
+        tags: Some(Regex::new(r"^\s*:(?i)(?:keywords|tags): *(.*)").unwrap()),
+        description: Some(Regex::new(r"^\s*:(?i)description: *(.*)").unwrap()),
+        keyword: Some(Regex::new(r"^:([\w-]+): *(.*)").unwrap()),
+        property: None,
+        drawer: None,
+        planning: None,
+    };
+}
+
+// The underline characters of headings from the outermost level to the
+// innermost one. reST itself ranks them by first use in a document, this
+// fixed order keeps the levels stable while a single heading is parsed.
+const ADORNMENTS: &str = "=-~^\"'`#*+:._";
+
+// The character a line is made of, if it can underline a heading.
+fn adornment(line: &str) -> Option<char> {
+    let line = line.trim_end();
+    let first = line.chars().next()?;
+    (line.len() > 1 && first.is_ascii_punctuation() && line.chars().all(|c| c == first))
+        .then_some(first)
+}
+
+fn heading_level(adornment: char) -> usize {
+    ADORNMENTS
+        .find(adornment)
+        .map_or(ADORNMENTS.len() + 1, |level| level + 1)
+}
+
+pub(crate) struct Rst;
+
+impl NoteFormat for Rst {
+    fn name(&self) -> &'static str {
+        "reStructuredText"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["rst"]
+    }
+
+    fn classify_line(
+        &self,
+        line: &str,
+        line_number: usize,
+        ratings: &RatingScale,
+    ) -> Option<LineType> {
+        classify_line(line, line_number, &RE_RST, ratings)
+    }
+
+    // A heading is a line of text followed by an underline at least as long as it.
+    fn classify_with_next(
+        &self,
+        line: &str,
+        next: Option<&str>,
+        line_number: usize,
+        ratings: &RatingScale,
+    ) -> Option<LineType> {
+        let title = line.trim_end();
+        let underline = next.map(str::trim_end).unwrap_or_default();
+        if let Some(marker) = adornment(underline) {
+            if !title.is_empty()
+                && !title.starts_with(char::is_whitespace)
+                && adornment(title).is_none()
+                && underline.chars().count() >= title.chars().count()
+            {
+                return Some(LineType::Heading(Heading {
+                    line_number,
+                    title: title.to_string(),
+                    level: heading_level(marker),
+                    heading: vec![],
+                    links: vec![],
+                    properties: BTreeMap::new(),
+                    tags: vec![],
+                    inherited_tags: vec![],
+                    state: None,
+                    priority: None,
+                    scheduled: None,
+                    deadline: None,
+                }));
+            }
+        }
+        self.classify_line(line, line_number, ratings)
+    }
+
+    fn find_links(&self, line: &str, line_number: usize, ratings: &RatingScale) -> Vec<LinkSpan> {
+        find_links(line, line_number, &RE_RST, ratings)
+    }
+
+    fn link_markup(&self, link: &str, name: &str) -> String {
+        format!("`{} <{}>`_", name, link)
+    }
+
+    // Headings span two lines, which the heading edits can't write, so they
+    // are refused for reST notes.
+    fn heading_marker(&self) -> Option<char> {
+        None
+    }
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{config::config::RatingScale, error::LinkorgError};
+
+use super::parse::{read_data_file, FileData};
+
+// Raised whenever the parsed data changes shape, so that an index written by an
+// older version is parsed again instead of missing the new fields.
+const INDEX_VERSION: u32 = 10;
+
+// What a file looked like on disk when it was parsed, if either changes the
+// file is parsed again.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Result<Stamp, LinkorgError> {
+        let meta_data =
+            fs::metadata(path).map_err(|err| LinkorgError::Io(path.to_path_buf(), err))?;
+        Ok(Stamp {
+            modified: meta_data.modified().ok(),
+            len: meta_data.len(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    stamp: Stamp,
+    data: FileData,
+}
+
+// The parsed notes, kept next to the config between runs so that only the
+// files changed since then have to be parsed again.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Index {
+    #[serde(default)]
+    version: u32,
+    // Likeability ranks depend on the scale the files were parsed with.
+    ratings: RatingScale,
+    entries: BTreeMap<PathBuf, Entry>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Index {
+            version: INDEX_VERSION,
+            ratings: RatingScale::default(),
+            entries: BTreeMap::new(),
+            dirty: false,
+        }
+    }
+}
+
+impl Index {
+    // A missing, unreadable or outdated index is rebuilt from scratch.
+    pub fn load(path: &Path) -> Index {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|index| serde_json::from_str::<Index>(&index).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_default()
+    }
+
+    // Writes the index out if anything was parsed since it was loaded.
+    pub fn save(&mut self, path: &Path) -> Result<(), LinkorgError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let index = serde_json::to_string(self)
+            .map_err(|err| LinkorgError::Io(path.to_path_buf(), err.into()))?;
+        fs::write(path, index).map_err(|err| LinkorgError::Io(path.to_path_buf(), err))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn use_ratings(&mut self, ratings: &RatingScale) {
+        if &self.ratings != ratings {
+            self.ratings = ratings.clone();
+            self.entries.clear();
+            self.dirty = true;
+        }
+    }
+
+    fn update(&mut self, path: &Path) -> Result<(), LinkorgError> {
+        let stamp = Stamp::of(path)?;
+        if self
+            .entries
+            .get(path)
+            .is_some_and(|entry| entry.stamp == stamp)
+        {
+            return Ok(());
+        }
+        let data = read_data_file(path.to_path_buf(), &self.ratings)?;
+        self.entries
+            .insert(path.to_path_buf(), Entry { stamp, data });
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn forget(&mut self, path: &Path) {
+        if self.entries.remove(path).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    // The data of one file, parsing it again only if it changed on disk.
+    pub fn file_data(
+        &mut self,
+        path: &Path,
+        ratings: &RatingScale,
+    ) -> Result<&FileData, LinkorgError> {
+        self.use_ratings(ratings);
+        if let Err(err) = self.update(path) {
+            self.forget(path);
+            return Err(err);
+        }
+        Ok(&self.entries[path].data)
+    }
+
+    // Brings the index in line with `files`, dropping every other file. Files
+    // which can't be read are left out instead of failing the whole refresh,
+    // the same way find_data_files skips unreadable entries.
+    pub fn refresh(&mut self, files: &[PathBuf], ratings: &RatingScale) {
+        self.use_ratings(ratings);
+        let files: BTreeSet<&PathBuf> = files.iter().collect();
+        let removed: Vec<PathBuf> = self
+            .entries
+            .keys()
+            .filter(|path| !files.contains(path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.forget(&path);
+        }
+        for path in files {
+            if self.update(path).is_err() {
+                self.forget(path);
+            }
+        }
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = (&Path, &FileData)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_path(), &entry.data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn indexing_only_changed_files() {
+        let dir = test_dir("linkorg_indexing_only_changed_files");
+        let note = dir.join("note.org");
+        let index_path = dir.join("index.json");
+        fs::write(&note, "* Books\n[[Link 1][Name 1]]\n").unwrap();
+
+        let ratings = RatingScale::default();
+        let mut index = Index::load(&index_path);
+        assert_eq!(index.file_data(&note, &ratings).unwrap().heading.len(), 1);
+        index.save(&index_path).unwrap();
+
+        let mut index = Index::load(&index_path);
+        assert_eq!(index.file_data(&note, &ratings).unwrap().heading.len(), 1);
+        assert!(!index.dirty);
+
+        fs::write(&note, "* Books\n[[Link 1][Name 1]]\n* Videos\n").unwrap();
+        assert_eq!(index.file_data(&note, &ratings).unwrap().heading.len(), 2);
+        assert!(index.dirty);
+    }
+
+    #[test]
+    fn rebuilding_outdated_index() {
+        let dir = test_dir("linkorg_rebuilding_outdated_index");
+        let note = dir.join("note.org");
+        let index_path = dir.join("index.json");
+        fs::write(&note, "* Books\n").unwrap();
+
+        let mut index = Index::load(&index_path);
+        index.file_data(&note, &RatingScale::default()).unwrap();
+        index.save(&index_path).unwrap();
+        assert_eq!(Index::load(&index_path).notes().count(), 1);
+
+        let outdated = fs::read_to_string(&index_path)
+            .unwrap()
+            .replace(&format!("\"version\":{}", INDEX_VERSION), "\"version\":0");
+        fs::write(&index_path, outdated).unwrap();
+        assert_eq!(Index::load(&index_path).notes().count(), 0);
+    }
+
+    #[test]
+    fn refreshing_index() {
+        let dir = test_dir("linkorg_refreshing_index");
+        let first = dir.join("first.org");
+        let second = dir.join("second.md");
+        fs::write(&first, "* First\n").unwrap();
+        fs::write(&second, "# Second\n").unwrap();
+
+        let ratings = RatingScale::default();
+        let mut index = Index::default();
+        index.refresh(&[first.clone(), second.clone()], &ratings);
+        assert_eq!(index.notes().count(), 2);
+
+        fs::remove_file(&second).unwrap();
+        index.refresh(&[first.clone(), second.clone()], &ratings);
+        assert_eq!(
+            index.notes().map(|(path, _)| path).collect::<Vec<_>>(),
+            vec![first.as_path()]
+        );
+
+        index.refresh(&[], &ratings);
+        assert_eq!(index.notes().count(), 0);
+    }
+
+    #[test]
+    fn reparsing_after_ratings_change() {
+        let dir = test_dir("linkorg_reparsing_after_ratings_change");
+        let note = dir.join("note.org");
+        fs::write(&note, "[[Link 1][Name 1]] (Loved)\n").unwrap();
+
+        let mut index = Index::default();
+        let data = index.file_data(&note, &RatingScale::default()).unwrap();
+        assert_eq!(data.links[0].likeability, None);
+
+        let ratings = RatingScale(vec!["Loved".to_string()]);
+        let data = index.file_data(&note, &ratings).unwrap();
+        assert_eq!(
+            data.links[0].likeability.as_ref().map(|val| val.rank),
+            Some(Some(0))
+        );
+    }
+}
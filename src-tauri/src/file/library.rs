@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+
+use super::parse::{FileData, Link, Progress};
+use super::search::{note_links, LinkEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum LinkSort {
+    Name,
+    Rating,
+    Progress,
+    // The day the link was added.
+    Date,
+}
+
+// One page of the links of every note, `total` being the number of links on
+// all the pages.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct LinkPage {
+    pub total: usize,
+    pub links: Vec<LinkEntry>,
+}
+
+// How far along a link is: untouched links first, then the started ones by how
+// much of them was read and the finished ones last. Pages, percentages and
+// seconds are compared as they are, so links mixing them only sort roughly.
+fn progress_key(progress: &Progress) -> (u8, f64) {
+    match progress {
+        Progress::NotStarted => (0, 0.0),
+        Progress::Page(page) => (1, *page as f64),
+        Progress::Percentage(percentage) => (1, *percentage as f64),
+        Progress::Chapter(chapter) => (
+            1,
+            chapter
+                .split(|c: char| !c.is_ascii_digit())
+                .next()
+                .and_then(|number| number.parse().ok())
+                .unwrap_or(0.0),
+        ),
+        Progress::Timestamp(seconds) => (1, *seconds as f64),
+        Progress::Other(_) => (1, 0.0),
+        Progress::Done => (2, 0.0),
+    }
+}
+
+// Compares two links which both have something to sort on.
+fn compare(sort: LinkSort, a: &Link, b: &Link) -> Ordering {
+    match sort {
+        LinkSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        LinkSort::Rating => {
+            let rank = |link: &Link| link.likeability.as_ref().and_then(|val| val.rank);
+            rank(a).cmp(&rank(b))
+        }
+        LinkSort::Progress => {
+            let key = |link: &Link| link.read_till.as_ref().map_or((0, 0.0), progress_key);
+            let ((a_rank, a_amount), (b_rank, b_amount)) = (key(a), key(b));
+            a_rank.cmp(&b_rank).then(a_amount.total_cmp(&b_amount))
+        }
+        LinkSort::Date => a.added.cmp(&b.added),
+    }
+}
+
+fn has_key(sort: LinkSort, link: &Link) -> bool {
+    match sort {
+        LinkSort::Name => true,
+        LinkSort::Rating => link
+            .likeability
+            .as_ref()
+            .is_some_and(|val| val.rank.is_some()),
+        LinkSort::Progress => link.read_till.is_some(),
+        LinkSort::Date => link.added.is_some(),
+    }
+}
+
+// Every link of the notes sorted on `sort`, skipping the first `offset` and
+// keeping at most `limit` of them. Links with nothing to sort on, like unrated
+// ones when sorting by rating, come last either way and keep the order they are
+// written in.
+pub fn library_page<'a>(
+    notes: impl IntoIterator<Item = (&'a Path, &'a FileData)>,
+    sort: LinkSort,
+    descending: bool,
+    offset: usize,
+    limit: Option<usize>,
+) -> LinkPage {
+    let mut links = note_links(notes);
+    links.sort_by(|a, b| {
+        match (has_key(sort, &a.link), has_key(sort, &b.link)) {
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+        let order = compare(sort, &a.link, &b.link);
+        if descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+
+    let total = links.len();
+    let links = links
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    LinkPage { total, links }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::RatingScale;
+    use crate::file::format::{org::Org, NoteFormat};
+    use std::path::PathBuf;
+
+    fn names(page: &LinkPage) -> Vec<&str> {
+        page.links
+            .iter()
+            .map(|entry| entry.link.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn sorting_and_paging_library() {
+        let ratings = RatingScale::default();
+        let parse = |name: &str, text: &str| {
+            let lines: Vec<String> = text.lines().map(|line| line.to_string()).collect();
+            Org.parse(name.to_string(), &lines, &ratings)
+        };
+        let books = parse(
+            "books.org",
+            "[[1][kafka]] (Good read) -- after 20 -- added 2024-02-01\n* Fiction\n[[2][Dune]] -- done -- added 2023-05-01",
+        );
+        let papers = parse(
+            "papers.org",
+            "[[3][Attention]] (Must read) -- after 3\n[[4][BigTable]] -- not started -- added 2024-01-01",
+        );
+        let (books_path, papers_path) = (PathBuf::from("books.org"), PathBuf::from("papers.org"));
+        let notes = [
+            (books_path.as_path(), &books),
+            (papers_path.as_path(), &papers),
+        ];
+        let page =
+            |sort, descending, offset, limit| library_page(notes, sort, descending, offset, limit);
+
+        let all = page(LinkSort::Name, false, 0, None);
+        assert_eq!(all.total, 4);
+        assert_eq!(names(&all), vec!["Attention", "BigTable", "Dune", "kafka"]);
+        assert_eq!(all.links[2].breadcrumb, vec!["Fiction"]);
+        assert_eq!(all.links[2].file, books_path);
+
+        let rated = page(LinkSort::Rating, false, 0, None);
+        assert_eq!(
+            names(&rated),
+            vec!["Attention", "kafka", "Dune", "BigTable"]
+        );
+
+        let progress = page(LinkSort::Progress, true, 0, None);
+        assert_eq!(
+            names(&progress),
+            vec!["Dune", "kafka", "Attention", "BigTable"]
+        );
+
+        let dated = page(LinkSort::Date, true, 1, Some(2));
+        assert_eq!(dated.total, 4);
+        assert_eq!(names(&dated), vec!["BigTable", "Dune"]);
+        assert!(page(LinkSort::Date, false, 4, Some(2)).links.is_empty());
+    }
+}
@@ -1,2 +1,11 @@
+pub mod agenda;
+pub mod date;
+pub mod duplicates;
+pub mod edit;
 pub mod file;
+pub mod format;
+pub mod index;
+pub mod library;
 pub mod parse;
+pub mod search;
+pub mod watch;
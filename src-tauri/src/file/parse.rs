@@ -1,61 +1,192 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::{config::config::RatingScale, error::LinkorgError};
+
+use super::date::NoteDate;
+use super::format::{format_of, NoteFormat};
+
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Lines};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+// The regular expressions of a line based format. `link_groups` are the
+// capture groups the link can be in and `name_group` the one of its name in
+// `links`. `heading` names its groups: `level`, `title` and optionally the `:`
+// separated `tags`, the todo `state` and the `priority` cookie. `planning`
+// finds the `SCHEDULED:` and `DEADLINE:` timestamps of a heading, on its own
+// line or the line below it.
+// Formats whose headings span two lines have no `heading`, and formats
+// which keep their metadata in a block at the top of the note have no metadata
+// expressions. `keyword` and `property` capture a name and its value, and
+// `drawer` the name of the `:NAME:` line opening a drawer or its `:END:`.
 #[derive(Debug)]
 pub struct RegexFileCollections {
-    links: Regex,
-    heading: Regex,
-    title: Regex,
-    date: Regex,
-    tags: Regex,
-    description: Regex,
+    pub(crate) links: Regex,
+    pub(crate) link_groups: &'static [usize],
+    pub(crate) name_group: usize,
+    pub(crate) heading: Option<Regex>,
+    pub(crate) title: Option<Regex>,
+    pub(crate) date: Option<Regex>,
+    pub(crate) tags: Option<Regex>,
+    pub(crate) description: Option<Regex>,
+    pub(crate) keyword: Option<Regex>,
+    pub(crate) property: Option<Regex>,
+    pub(crate) drawer: Option<Regex>,
+    pub(crate) planning: Option<Regex>,
 }
 
 lazy_static! {
-    static ref RE_ORG: RegexFileCollections = RegexFileCollections {
-                      links: Regex::new(r"^(?: *\|)? *\[\[(.*)\]\[(.*)\]\](?: *\|)? *(?:\(([^()]*[^[Rr]ead])\))?(?: *\|)? *(?:\(([^()]*[Rr]ead)\))?(?: *\|)?.*-- ?after ?([\w\.]*) *(?: *\|)?").unwrap(),
-                      heading: Regex::new(r"^(\*+) *(\w.*)").unwrap(),
-                      title: Regex::new(r"#\+(?i)title: *(.*)").unwrap(),
-                      date: Regex::new(r"#\+(?i)date: *\[(.*)\]").unwrap(),
-                      tags: Regex::new(r"#\+(?i)filetags: *:(.*):*").unwrap(),
-                      description: Regex::new(r"#\+(?i)description: *(.*)").unwrap(),
-    };
-
-    static ref RE_MD: RegexFileCollections = RegexFileCollections {
-                      links: Regex::new(r"^(?: *\|)? *\[(.*)\]\((.*?)\)(?: *\|)? *(?:\(([^()]*[^[Rr]ead])\))?(?: *\|)? *(?:\(([^()]*[Rr]ead)\))?(?: *\|)?.*-- ?after ?([\w\.]*) *(?: *\|)?").unwrap(),
-                      heading: Regex::new(r"^(#+) *(\w.*)").unwrap(),
-                      title: Regex::new(r"(?i)title: *(.*)").unwrap(),
-                      date: Regex::new(r"(?i)date: *\[(.*)\]").unwrap(),
-                      tags: Regex::new(r"(?i)filetags: *:(.*):*").unwrap(),
-                      description: Regex::new(r"(?i)description: *(.*)").unwrap(),
-    };
+    static ref RE_ANNOTATION: Regex = Regex::new(r"\(([^()]*)\)").unwrap();
+    static ref RE_READ_TILL: Regex = Regex::new(r"--\s*(?:after\s*((?i:ch(?:apter|\.)?)\s*\d[\w\.]*|[\w\.:%]+)|((?i)done|finished|not\s+started))").unwrap();
+    static ref RE_CHAPTER: Regex = Regex::new(r"^(?i)ch(?:apter|\.)?\s*(\d[\w\.]*)$").unwrap();
+    // `-- added 2024-01-01` and the date a link was finished on after its
+    // `-- done`, either in ISO 8601 or as an org timestamp.
+    static ref RE_DATED: Regex = Regex::new(r"--\s*((?i)added|done|finished)\s+(\d{4}-\d{2}-\d{2}(?:T[\d:]+)?|[<\[]\d{4}-\d{2}-\d{2}[^<>\[\]]*[>\]])").unwrap();
+    // An org timestamp written on its own after a link, the day it was added.
+    static ref RE_TIMESTAMP: Regex = Regex::new(r"[<\[]\d{4}-\d{2}-\d{2}[^<>\[\]]*[>\]]").unwrap();
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Link {
     pub name: String,
     pub link: String,
-    pub read_till: String,
+    pub read_till: Option<Progress>,
     pub description: Option<String>,
-    pub likeability: Option<String>,
+    pub likeability: Option<Likeability>,
     pub line_number: usize,
+    #[serde(default)]
+    pub added: Option<NoteDate>,
+    #[serde(default)]
+    pub finished: Option<NoteDate>,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+// A likeability annotation together with its place on the configured rating
+// scale, `rank` 0 being the most liked. Labels which are not on the scale keep
+// their text with no rank.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Likeability {
+    pub label: String,
+    pub rank: Option<usize>,
+}
+
+// How far a link has been read or watched, taken from its `-- after X`
+// annotation.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", content = "value")]
+pub enum Progress {
+    NotStarted,
+    Page(u32),
+    Percentage(f32),
+    Chapter(String),
+    // Seconds into a video
+    Timestamp(u32),
+    Done,
+    Other(String),
+}
+
+impl Progress {
+    pub fn parse(text: &str) -> Progress {
+        let text = text.trim();
+        let lower = text.to_lowercase();
+        if lower == "done" || lower == "finished" {
+            Progress::Done
+        } else if lower.split_whitespace().collect::<Vec<_>>() == ["not", "started"] {
+            Progress::NotStarted
+        } else if let Ok(page) = text.parse::<u32>() {
+            Progress::Page(page)
+        } else if let Some(percentage) = text
+            .strip_suffix('%')
+            .and_then(|val| val.parse::<f32>().ok())
+            .filter(|percentage| percentage.is_finite())
+        {
+            Progress::Percentage(percentage)
+        } else if let Some(val) = RE_CHAPTER.captures(text) {
+            Progress::Chapter(val.get(1).unwrap().as_str().to_string())
+        } else if let Some(seconds) = Progress::parse_timestamp(text) {
+            Progress::Timestamp(seconds)
+        } else {
+            Progress::Other(text.to_string())
+        }
+    }
+
+    fn parse_timestamp(text: &str) -> Option<u32> {
+        let parts: Vec<&str> = text.split(':').collect();
+        if !(2..=3).contains(&parts.len()) {
+            return None;
+        }
+        parts.iter().try_fold(0, |seconds, part| {
+            Some(seconds * 60 + part.parse::<u32>().ok()?)
+        })
+    }
+
+    // The annotation which is written back to the file for this progress.
+    pub fn annotation(&self) -> String {
+        match self {
+            Progress::NotStarted => "-- not started".to_string(),
+            Progress::Page(page) => format!("-- after {}", page),
+            Progress::Percentage(percentage) => format!("-- after {}%", percentage),
+            Progress::Chapter(chapter) => format!("-- after chapter {}", chapter),
+            Progress::Timestamp(seconds) if *seconds >= 3600 => format!(
+                "-- after {}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            ),
+            Progress::Timestamp(seconds) => {
+                format!("-- after {}:{:02}", seconds / 60, seconds % 60)
+            }
+            Progress::Done => "-- done".to_string(),
+            Progress::Other(text) => format!("-- after {}", text),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum TodoState {
+    Todo,
+    Done,
+}
+
+impl TodoState {
+    fn parse(keyword: &str) -> Option<TodoState> {
+        match keyword {
+            "TODO" => Some(TodoState::Todo),
+            "DONE" => Some(TodoState::Done),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Heading {
     pub title: String,
     pub level: usize,
     pub line_number: usize,
     pub heading: Vec<Heading>,
     pub links: Vec<Link>,
+    // The properties drawer right under the heading.
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // The tags of the file and of every heading above this one.
+    #[serde(default)]
+    pub inherited_tags: Vec<String>,
+    #[serde(default)]
+    pub state: Option<TodoState>,
+    #[serde(default)]
+    pub priority: Option<char>,
+    #[serde(default)]
+    pub scheduled: Option<NoteDate>,
+    #[serde(default)]
+    pub deadline: Option<NoteDate>,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct FileData {
     pub file_name: String,
     pub file_meta_data: FileMetaData,
@@ -64,12 +195,19 @@ pub struct FileData {
     pub links: Vec<Link>,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct FileMetaData {
     pub file_title: String,
     pub file_description: String,
-    pub file_date: String,
+    #[serde(default)]
+    pub file_date: Option<NoteDate>,
     pub file_tags: Vec<String>,
+    // Metadata keys linkorg has no use for, kept as they were written.
+    #[serde(default)]
+    pub file_extra: BTreeMap<String, serde_json::Value>,
+    // Why the metadata couldn't be read, like front matter which isn't valid.
+    #[serde(default)]
+    pub file_error: Option<String>,
 }
 
 impl Default for FileMetaData {
@@ -77,8 +215,10 @@ impl Default for FileMetaData {
         FileMetaData {
             file_title: "No title".to_string(),
             file_description: "No description".to_string(),
-            file_date: "No creation Date found".to_string(),
+            file_date: None,
             file_tags: vec![],
+            file_extra: BTreeMap::new(),
+            file_error: None,
         }
     }
 }
@@ -92,192 +232,473 @@ where
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
-enum FileType {
-    Org,
-    Md,
-}
-
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
-enum LineType {
-    Link(Link),
+pub(crate) enum LineType {
+    Links(Vec<Link>),
     Heading(Heading),
     MetaData(MetaData),
+    // A property of the heading above, or of the file before the first heading,
+    // when it is inside a properties drawer.
+    Property(String, String),
+    // The start of a drawer, or `END` at its end.
+    Drawer(String),
+    // The scheduled and deadline timestamps of the heading above.
+    Planning(Planning),
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Planning {
+    pub(crate) scheduled: Option<NoteDate>,
+    pub(crate) deadline: Option<NoteDate>,
+}
+
+impl Planning {
+    // Takes the planning keywords out of `text`, returning what is left of it.
+    fn take(regex: &Option<Regex>, text: &str) -> (Planning, String) {
+        let mut planning = Planning::default();
+        let Some(regex) = regex else {
+            return (planning, text.to_string());
+        };
+        for val in regex.captures_iter(text) {
+            let timestamp = NoteDate::parse(&val[2]);
+            match &val[1] {
+                "SCHEDULED" => planning.scheduled = timestamp,
+                "DEADLINE" => planning.deadline = timestamp,
+                _ => {}
+            }
+        }
+        let rest = regex.replace_all(text, "").trim().to_string();
+        (planning, rest)
+    }
+
+    // The planning of a line which holds nothing else.
+    fn of_line(regex: &Option<Regex>, line: &str) -> Option<Planning> {
+        if !regex.as_ref().is_some_and(|regex| regex.is_match(line)) {
+            return None;
+        }
+        let (planning, rest) = Planning::take(regex, line);
+        rest.is_empty().then_some(planning)
+    }
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
-enum MetaData {
+pub(crate) enum MetaData {
     Title(String),
     Description(String),
-    Date(String),
+    Date(NoteDate),
     Tags(Vec<String>),
+    // Any other keyword, with its name in lowercase.
+    Keyword(String, String),
+}
+
+// A link found on a line, along with the byte offsets of the pieces it is made
+// of so that the line can be rewritten in place.
+#[derive(Debug, PartialEq)]
+pub(crate) struct LinkSpan {
+    pub(crate) link: Link,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) description: Option<usize>,
+    pub(crate) likeability: Option<usize>,
+    pub(crate) read_till: Option<usize>,
+    pub(crate) added: Option<usize>,
+    // Where the address of the link is.
+    pub(crate) url: Range<usize>,
 }
 
-fn classify_line(
-    line: &String,
+// Finds every link on a line. The annotations following a link, up to the next
+// link on the line, belong to it: a parenthesised text which is on the rating
+// scale or ends in "read" is its likeability, any other one its description,
+// `-- after X` its read_till, `-- added DATE` or a lone timestamp the day it was
+// added and a date after `-- done` the day it was finished.
+pub(crate) fn find_links(
+    line: &str,
     line_number: usize,
     regex_collection: &RegexFileCollections,
-    file_type: &FileType,
-) -> Option<LineType> {
-    if let Some(val) = regex_collection.links.captures(line.as_str()) {
-        let description = match val.get(3).map(|m| m.as_str()) {
-            Some(val) => Some(String::from(val)),
-            None => None,
-        };
-        let likeability = match val.get(4).map(|m| m.as_str()) {
-            Some(val) => Some(String::from(val)),
-            None => None,
-        };
-        let (link_position, name_position) = match file_type {
-            FileType::Org => (1, 2),
-            FileType::Md => (2, 1),
-        };
+    ratings: &RatingScale,
+) -> Vec<LinkSpan> {
+    let matches: Vec<_> = regex_collection.links.captures_iter(line).collect();
 
-        Some(LineType::Link(Link {
-            line_number,
-            link: String::from(val.get(link_position).map(|m| m.as_str()).unwrap()),
-            name: String::from(val.get(name_position).map(|m| m.as_str()).unwrap()),
-            read_till: String::from(val.get(5).map(|m| m.as_str()).unwrap()),
-            description,
-            likeability,
-        }))
-    } else if let Some(val) = regex_collection.heading.captures(line.as_str()) {
+    matches
+        .iter()
+        .enumerate()
+        .map(|(i, val)| {
+            let whole = val.get(0).unwrap();
+            let url = regex_collection
+                .link_groups
+                .iter()
+                .find_map(|group| val.get(*group))
+                .unwrap();
+            let link = url.as_str().to_string();
+            let name = match val.get(regex_collection.name_group).map(|m| m.as_str()) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => link.clone(),
+            };
+            let annotation_end = matches
+                .get(i + 1)
+                .map_or(line.len(), |next| next.get(0).unwrap().start());
+            let annotations = &line[whole.end()..annotation_end];
+
+            let mut span = LinkSpan {
+                link: Link {
+                    name,
+                    link,
+                    read_till: None,
+                    description: None,
+                    likeability: None,
+                    line_number,
+                    added: None,
+                    finished: None,
+                },
+                start: whole.start(),
+                end: whole.end(),
+                description: None,
+                likeability: None,
+                read_till: None,
+                added: None,
+                url: url.range(),
+            };
+            for annotation in RE_ANNOTATION.captures_iter(annotations) {
+                let (outer, text) = (annotation.get(0).unwrap(), annotation.get(1).unwrap());
+                let text = text.as_str().trim();
+                let start = whole.end() + outer.start();
+                let rank = ratings.rank(text);
+                if rank.is_some() || text.to_lowercase().ends_with("read") {
+                    if span.likeability.is_none() {
+                        span.link.likeability = Some(Likeability {
+                            label: text.to_string(),
+                            rank,
+                        });
+                        span.likeability = Some(start);
+                    }
+                } else if span.description.is_none() {
+                    span.link.description = Some(text.to_string());
+                    span.description = Some(start);
+                }
+                span.end = span.end.max(whole.end() + outer.end());
+            }
+            if let Some(read_till) = RE_READ_TILL.captures(annotations) {
+                let outer = read_till.get(0).unwrap();
+                let text = read_till.get(1).or(read_till.get(2)).unwrap().as_str();
+                span.link.read_till = Some(Progress::parse(text));
+                span.read_till = Some(whole.end() + outer.start());
+                span.end = span.end.max(whole.end() + outer.end());
+            }
+            let mut dated = vec![];
+            for val in RE_DATED.captures_iter(annotations) {
+                let outer = val.get(0).unwrap();
+                let date = NoteDate::parse(&val[2]);
+                if val[1].eq_ignore_ascii_case("added") {
+                    if span.added.is_none() {
+                        span.link.added = date;
+                        span.added = Some(whole.end() + outer.start());
+                    }
+                } else if span.link.finished.is_none() {
+                    span.link.finished = date;
+                }
+                dated.push(outer.range());
+                span.end = span.end.max(whole.end() + outer.end());
+            }
+            if span.added.is_none() {
+                if let Some(outer) = RE_TIMESTAMP
+                    .find_iter(annotations)
+                    .find(|timestamp| !dated.iter().any(|range| range.contains(&timestamp.start())))
+                {
+                    span.link.added = NoteDate::parse(outer.as_str());
+                    span.added = Some(whole.end() + outer.start());
+                    span.end = span.end.max(whole.end() + outer.end());
+                }
+            }
+            span
+        })
+        .collect()
+}
+
+pub(crate) fn classify_line(
+    line: &str,
+    line_number: usize,
+    regex_collection: &RegexFileCollections,
+    ratings: &RatingScale,
+) -> Option<LineType> {
+    let captures = |regex: &Option<Regex>| regex.as_ref().and_then(|regex| regex.captures(line));
+    if let Some(val) = captures(&regex_collection.heading) {
+        let (planning, title) = Planning::take(&regex_collection.planning, &val["title"]);
         Some(LineType::Heading(Heading {
             line_number,
-            title: String::from(val.get(2).map(|m| m.as_str()).unwrap()),
-            level: val.get(1).map(|m| m.as_str()).unwrap().len(),
+            title,
+            level: val["level"].len(),
             heading: vec![],
             links: vec![],
+            properties: BTreeMap::new(),
+            tags: val
+                .name("tags")
+                .map(|m| {
+                    m.as_str()
+                        .split(':')
+                        .filter(|tag| !tag.is_empty())
+                        .map(|tag| tag.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            inherited_tags: vec![],
+            state: val.name("state").and_then(|m| TodoState::parse(m.as_str())),
+            priority: val.name("priority").and_then(|m| m.as_str().chars().next()),
+            scheduled: planning.scheduled,
+            deadline: planning.deadline,
         }))
-    } else if let Some(val) = regex_collection.title.captures(line.as_str()) {
+    } else if let Some(planning) = Planning::of_line(&regex_collection.planning, line) {
+        Some(LineType::Planning(planning))
+    } else if regex_collection.links.is_match(line) {
+        Some(LineType::Links(
+            find_links(line, line_number, regex_collection, ratings)
+                .into_iter()
+                .map(|span| span.link)
+                .collect(),
+        ))
+    } else if let Some(val) = captures(&regex_collection.title) {
         Some(LineType::MetaData(MetaData::Title(String::from(
             val.get(1).map(|m| m.as_str()).unwrap(),
         ))))
-    } else if let Some(val) = regex_collection.description.captures(line.as_str()) {
+    } else if let Some(val) = captures(&regex_collection.description) {
         Some(LineType::MetaData(MetaData::Description(String::from(
             val.get(1).map(|m| m.as_str()).unwrap(),
         ))))
-    } else if let Some(val) = regex_collection.date.captures(line.as_str()) {
-        Some(LineType::MetaData(MetaData::Date(String::from(
-            val.get(1).map(|m| m.as_str()).unwrap(),
-        ))))
-    } else if let Some(val) = regex_collection.tags.captures(line.as_str()) {
+    } else if let Some(val) = captures(&regex_collection.date) {
+        // A date which can't be read is kept as it was written.
+        let text = val[1].trim();
+        Some(LineType::MetaData(match NoteDate::parse(text) {
+            Some(date) => MetaData::Date(date),
+            None => MetaData::Keyword("date".to_string(), text.to_string()),
+        }))
+    } else if let Some(val) = captures(&regex_collection.tags) {
         let tags_str: Vec<_> = val
             .get(1)
             .map(|m| m.as_str())
             .unwrap()
-            .split(":")
-            .filter(|x| *x != "")
+            .split([':', ','])
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
             .map(|s| s.to_string())
             .collect();
         Some(LineType::MetaData(MetaData::Tags(tags_str)))
+    } else if let Some(val) = captures(&regex_collection.keyword) {
+        Some(LineType::MetaData(MetaData::Keyword(
+            val[1].to_lowercase(),
+            val[2].trim().to_string(),
+        )))
+    } else if let Some(val) = captures(&regex_collection.drawer) {
+        Some(LineType::Drawer(val[1].to_uppercase()))
     } else {
-        None
+        captures(&regex_collection.property)
+            .map(|val| LineType::Property(val[1].to_string(), val[2].to_string()))
     }
 }
 
-fn parse_data_file(
-    data: &mut FileData,
-    file_meta_data: &mut FileMetaData,
-    lines: Lines<BufReader<File>>,
-    regex_collection: &RegexFileCollections,
-    file_type: &FileType,
-) {
-    let mut _current_links_vec = &mut data.links;
-    let mut _current_heading_vec = &mut data.heading;
-    let mut _current_level = 0;
-    let mut _len = 0;
-
-    for (line_number, line) in lines.enumerate() {
-        let line = line.unwrap();
-        match classify_line(&line, line_number + 1, &regex_collection, file_type) {
-            Some(LineType::Link(link)) => _current_links_vec.push(link),
-            Some(LineType::Heading(heading)) => {
-                _len = (&_current_heading_vec).len();
-                if _current_level > heading.level - 1 {
-                    _current_level = 0;
-                    _current_links_vec = &mut data.links;
-                    _current_heading_vec = &mut data.heading;
-                    _len = (&_current_heading_vec).len();
-                    while _len != 0 && (&_current_heading_vec[_len - 1]).level < heading.level - 1 {
-                        _current_level = (&_current_heading_vec[_len - 1]).level;
-                        unsafe {
-                            _current_links_vec =
-                                &mut *(&mut _current_heading_vec[_len - 1].links as *mut Vec<Link>);
-                        }
-                        _current_heading_vec = &mut _current_heading_vec[_len - 1].heading;
-                        _len = (&_current_heading_vec).len();
-                    }
-                }
-                _current_heading_vec.push(heading);
-                _len += 1;
-                _current_level = (&_current_heading_vec[_len - 1]).level;
-                unsafe {
-                    _current_links_vec =
-                        &mut *(&mut _current_heading_vec[_len - 1].links as *mut Vec<Link>);
-                }
-                _current_heading_vec = &mut _current_heading_vec[_len - 1].heading;
+// Headings which are still open are kept on a stack, with the innermost one on
+// top. A new heading closes every open heading at the same or a deeper level,
+// attaching each one to its parent, so level jumps like `*` followed by `***`
+// still nest the deeper heading under the closest shallower one.
+fn close_heading(data: &mut FileData, open_headings: &mut Vec<Heading>) {
+    if let Some(heading) = open_headings.pop() {
+        match open_headings.last_mut() {
+            Some(parent) => parent.heading.push(heading),
+            None => data.heading.push(heading),
+        }
+    }
+}
+
+fn inherit_headings_tags(headings: &mut [Heading], inherited: &[String]) {
+    for heading in headings {
+        heading.inherited_tags = inherited.to_vec();
+        let mut tags = inherited.to_vec();
+        for tag in &heading.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
             }
-            Some(LineType::MetaData(metadata)) => match metadata {
-                MetaData::Title(title) => file_meta_data.file_title = title,
-                MetaData::Description(description) => file_meta_data.file_description = description,
-                MetaData::Date(date) => file_meta_data.file_date = date,
-                MetaData::Tags(tags) => file_meta_data.file_tags = tags,
-            },
-            None => println!("Line can't be classified : {}", line.clone()),
         }
+        inherit_headings_tags(&mut heading.heading, &tags);
     }
 }
 
-pub fn read_data_file(path: PathBuf) -> FileData {
+// Hands the file tags and the tags of every heading down to the headings nested
+// under them. Has to run again whenever the file tags change.
+pub(crate) fn inherit_tags(data: &mut FileData) {
+    inherit_headings_tags(&mut data.heading, &data.file_meta_data.file_tags);
+}
+
+pub(crate) fn build_file_data<F: NoteFormat + ?Sized>(
+    format: &F,
+    file_name: String,
+    lines: &[String],
+    ratings: &RatingScale,
+) -> FileData {
     let mut data = FileData {
-        file_name: format!("{}", path.file_name().unwrap().to_str().unwrap()).to_string(),
+        file_name,
         file_meta_data: FileMetaData::default(),
         level: 0,
         heading: vec![],
         links: vec![],
     };
+    let mut open_headings: Vec<Heading> = vec![];
+    // Properties are only read inside a `:PROPERTIES:` drawer, the other drawers
+    // like `:LOGBOOK:` are left out.
+    let mut in_properties = false;
 
-    let (file_type, regex_collection) = match path.extension().unwrap().to_str().unwrap() {
-        "org" => (FileType::Org, &*RE_ORG),
-        "md" => (FileType::Md, &*RE_MD),
-        val => panic!("Not a supported file format {val}"),
-    };
-
-    unsafe {
-        let file_meta_data = &mut *(&mut data.file_meta_data as *mut FileMetaData);
-        if let Ok(lines) = read_lines(path) {
-            parse_data_file(
-                &mut data,
-                file_meta_data,
-                lines,
-                regex_collection,
-                &file_type,
-            );
+    for (index, line) in lines.iter().enumerate().skip(format.body_start(lines)) {
+        let next = lines.get(index + 1).map(|next| next.as_str());
+        match format.classify_with_next(line, next, index + 1, ratings) {
+            Some(LineType::Links(links)) => match open_headings.last_mut() {
+                Some(heading) => heading.links.extend(links),
+                None => data.links.extend(links),
+            },
+            Some(LineType::Heading(heading)) => {
+                while open_headings
+                    .last()
+                    .is_some_and(|open| open.level >= heading.level)
+                {
+                    close_heading(&mut data, &mut open_headings);
+                }
+                open_headings.push(heading);
+            }
+            Some(LineType::MetaData(metadata)) => match metadata {
+                MetaData::Title(title) => data.file_meta_data.file_title = title,
+                MetaData::Description(description) => {
+                    data.file_meta_data.file_description = description
+                }
+                MetaData::Date(date) => data.file_meta_data.file_date = Some(date),
+                MetaData::Tags(tags) => data.file_meta_data.file_tags = tags,
+                MetaData::Keyword(name, value) => {
+                    data.file_meta_data
+                        .file_extra
+                        .insert(name, serde_json::Value::String(value));
+                }
+            },
+            Some(LineType::Planning(planning)) => {
+                if let Some(heading) = open_headings.last_mut() {
+                    heading.scheduled = heading.scheduled.take().or(planning.scheduled);
+                    heading.deadline = heading.deadline.take().or(planning.deadline);
+                }
+            }
+            Some(LineType::Drawer(name)) => in_properties = name == "PROPERTIES",
+            Some(LineType::Property(_, _)) if !in_properties => {}
+            Some(LineType::Property(name, value)) => match open_headings.last_mut() {
+                Some(heading) => {
+                    heading.properties.insert(name, value);
+                }
+                None => {
+                    data.file_meta_data
+                        .file_extra
+                        .insert(name.to_lowercase(), serde_json::Value::String(value));
+                }
+            },
+            None => println!("Line can't be classified : {}", line),
         }
     }
+    while !open_headings.is_empty() {
+        close_heading(&mut data, &mut open_headings);
+    }
+    inherit_tags(&mut data);
     data
 }
 
+pub fn read_data_file(path: PathBuf, ratings: &RatingScale) -> Result<FileData, LinkorgError> {
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy().to_string(),
+        None => return Err(LinkorgError::UnsupportedFormat(path)),
+    };
+    let format = match format_of(&path) {
+        Some(format) => format,
+        None => return Err(LinkorgError::UnsupportedFormat(path)),
+    };
+
+    let lines = read_lines(&path)
+        .map_err(|err| LinkorgError::Io(path.clone(), err))?
+        .enumerate()
+        .map(|(line_number, line)| {
+            line.map_err(|err| match err.kind() {
+                io::ErrorKind::InvalidData => {
+                    LinkorgError::InvalidUtf8(path.to_path_buf(), line_number + 1)
+                }
+                _ => LinkorgError::Io(path.to_path_buf(), err),
+            })
+        })
+        .collect::<Result<Vec<String>, LinkorgError>>()?;
+    Ok(format.parse(file_name, &lines, ratings))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::file::format::{
+        asciidoc::RE_ADOC,
+        markdown::RE_MD,
+        org::{Org, RE_ORG},
+        rst::{Rst, RE_RST},
+    };
+
+    fn example_tags() -> Vec<String> {
+        ["testing", "rust", "orgmode", "linkorg"]
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect()
+    }
+
+    fn likeability(label: &str, rank: Option<usize>) -> Option<Likeability> {
+        Some(Likeability {
+            label: label.to_string(),
+            rank,
+        })
+    }
 
     #[test]
     fn classifying_org_test_heading() {
         let regex_collection = &*RE_ORG;
         assert_eq!(
-            classify_line(
-                &"** Testing".to_string(),
-                1,
-                regex_collection,
-                &FileType::Org
-            ),
+            classify_line("** Testing", 1, regex_collection, &RatingScale::default()),
             Some(LineType::Heading(Heading {
                 title: "Testing".to_string(),
                 level: 2,
                 line_number: 1,
                 heading: vec![],
                 links: vec![],
+                properties: BTreeMap::new(),
+                tags: vec![],
+                inherited_tags: vec![],
+                state: None,
+                priority: None,
+                scheduled: None,
+                deadline: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn classifying_org_test_todo_heading() {
+        match classify_line(
+            "** TODO [#A] Read Kafka book SCHEDULED: <2024-01-01> :books:",
+            1,
+            &RE_ORG,
+            &RatingScale::default(),
+        ) {
+            Some(LineType::Heading(heading)) => {
+                assert_eq!(heading.title, "Read Kafka book");
+                assert_eq!(heading.state, Some(TodoState::Todo));
+                assert_eq!(heading.priority, Some('A'));
+                assert_eq!(heading.scheduled, NoteDate::parse("2024-01-01"));
+                assert_eq!(heading.deadline, None);
+                assert_eq!(heading.tags, vec!["books"]);
+            }
+            val => panic!("Not a heading {:?}", val),
+        }
+        assert_eq!(
+            classify_line(
+                "  DEADLINE: <2024-01-01 Mon>",
+                1,
+                &RE_ORG,
+                &RatingScale::default()
+            ),
+            Some(LineType::Planning(Planning {
+                scheduled: None,
+                deadline: NoteDate::parse("2024-01-01"),
             }))
         );
     }
@@ -287,34 +708,121 @@ mod tests {
         let regex_collection = &*RE_ORG;
         assert_eq!(
             classify_line(
-                &"[[Link to test book][Table test book]] (Mediocure read)             -- after 8"
-                    .to_string(),
+                "[[Link to test book][Table test book]] (Mediocure read)             -- after 8",
                 1,
                 regex_collection,
-                &FileType::Org
+                &RatingScale::default()
             ),
-            Some(LineType::Link(Link {
+            Some(LineType::Links(vec![Link {
                 name: "Table test book".to_string(),
                 link: "Link to test book".to_string(),
-                read_till: "8".to_string(),
-                likeability: Some("Mediocure read".to_string()),
+                read_till: Some(Progress::Page(8)),
+                likeability: likeability("Mediocure read", None),
                 description: None,
                 line_number: 1,
-            }))
+                added: None,
+                finished: None,
+            }]))
         );
     }
 
     #[test]
     fn classifying_org_test_link_table() {
         let regex_collection = &*RE_ORG;
-        assert_eq!(classify_line(&"| [[Link to test book][Table test book]] | | (Mediocure read)|             -- after 8 |".to_string(),1,regex_collection,&FileType::Org),Some(LineType::Link( Link{ 
+        assert_eq!(classify_line("| [[Link to test book][Table test book]] | | (Mediocure read)|             -- after 8 |",1,regex_collection, &RatingScale::default()),Some(LineType::Links(vec![Link { 
             name: "Table test book".to_string(),
             link: "Link to test book".to_string(),
-            read_till: "8".to_string(),
-            likeability: Some("Mediocure read".to_string()),
+            read_till: Some(Progress::Page(8)),
+            likeability: likeability("Mediocure read", None),
             description: None,
             line_number: 1,
-        })));
+            added: None,
+            finished: None,
+        }])));
+    }
+
+    #[test]
+    fn classifying_org_test_multiple_links() {
+        let regex_collection = &*RE_ORG;
+        assert_eq!(
+            classify_line(
+                "- [[https://first.com][First]] (Good book) -- after 3 and [[https://second.com]] (Must read)",
+                1,
+                regex_collection, &RatingScale::default()
+            ),
+            Some(LineType::Links(vec![
+                Link {
+                    name: "First".to_string(),
+                    link: "https://first.com".to_string(),
+                    read_till: Some(Progress::Page(3)),
+                    likeability: None,
+                    description: Some("Good book".to_string()),
+                    line_number: 1,
+                    added: None,
+                    finished: None,
+                },
+                Link {
+                    name: "https://second.com".to_string(),
+                    link: "https://second.com".to_string(),
+                    read_till: None,
+                    likeability: likeability("Must read", Some(0)),
+                    description: None,
+                    line_number: 1,
+                    added: None,
+                    finished: None,
+                },
+            ]))
+        );
+    }
+
+    #[test]
+    fn classifying_org_test_bare_link() {
+        let regex_collection = &*RE_ORG;
+        assert_eq!(
+            classify_line(
+                "[[https://orgmode.org]]",
+                1,
+                regex_collection,
+                &RatingScale::default()
+            ),
+            Some(LineType::Links(vec![Link {
+                name: "https://orgmode.org".to_string(),
+                link: "https://orgmode.org".to_string(),
+                read_till: None,
+                likeability: None,
+                description: None,
+                line_number: 1,
+                added: None,
+                finished: None,
+            }]))
+        );
+    }
+
+    #[test]
+    fn classifying_org_test_heading_with_link() {
+        let regex_collection = &*RE_ORG;
+        assert_eq!(
+            classify_line(
+                "* Books from [[https://orgmode.org][org]]",
+                1,
+                regex_collection,
+                &RatingScale::default()
+            ),
+            Some(LineType::Heading(Heading {
+                title: "Books from [[https://orgmode.org][org]]".to_string(),
+                level: 1,
+                line_number: 1,
+                heading: vec![],
+                links: vec![],
+                properties: BTreeMap::new(),
+                tags: vec![],
+                inherited_tags: vec![],
+                state: None,
+                priority: None,
+                scheduled: None,
+                deadline: None,
+            }))
+        );
     }
 
     #[test]
@@ -322,10 +830,10 @@ mod tests {
         let regex_collection = &*RE_ORG;
         assert_eq!(
             classify_line(
-                &"#+title: testing title".to_string(),
+                "#+title: testing title",
                 1,
                 regex_collection,
-                &FileType::Org
+                &RatingScale::default()
             ),
             Some(LineType::MetaData(MetaData::Title(
                 "testing title".to_string()
@@ -338,10 +846,10 @@ mod tests {
         let regex_collection = &*RE_ORG;
         assert_eq!(
             classify_line(
-                &"#+description: testing description".to_string(),
+                "#+description: testing description",
                 1,
                 regex_collection,
-                &FileType::Org
+                &RatingScale::default()
             ),
             Some(LineType::MetaData(MetaData::Description(
                 "testing description".to_string()
@@ -354,13 +862,20 @@ mod tests {
         let regex_collection = &*RE_ORG;
         assert_eq!(
             classify_line(
-                &"#+date: [2023-07-10 Mon 17:00]".to_string(),
+                "#+date: [2023-07-10 Mon 17:00]",
                 1,
                 regex_collection,
-                &FileType::Org
+                &RatingScale::default()
             ),
             Some(LineType::MetaData(MetaData::Date(
-                "2023-07-10 Mon 17:00".to_string()
+                NoteDate::parse("2023-07-10 17:00").unwrap()
+            )))
+        );
+        assert_eq!(
+            classify_line("#+date: soon", 1, regex_collection, &RatingScale::default()),
+            Some(LineType::MetaData(MetaData::Keyword(
+                "date".to_string(),
+                "soon".to_string()
             )))
         );
     }
@@ -370,10 +885,10 @@ mod tests {
         let regex_collection = &*RE_ORG;
         assert_eq!(
             classify_line(
-                &"#+filetags: :tag1:tag2:tag3:".to_string(),
+                "#+filetags: :tag1:tag2:tag3:",
                 1,
                 regex_collection,
-                &FileType::Org
+                &RatingScale::default()
             ),
             Some(LineType::MetaData(MetaData::Tags(vec![
                 "tag1".to_string(),
@@ -385,21 +900,26 @@ mod tests {
 
     #[test]
     fn test_parsing_org() {
-        let parsed_input =
-            read_data_file(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../examples/test.org"));
+        let parsed_input = read_data_file(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../examples/test.org"),
+            &RatingScale::default(),
+        )
+        .unwrap();
 
         let expected_output = FileData {
             file_name: "test.org".to_string(),
             file_meta_data: FileMetaData {
                 file_title: "test title".to_string(),
                 file_description: "Test description to check parsing".to_string(),
-                file_date: "2023-07-10 Mon 17:00".to_string(),
+                file_date: NoteDate::parse("2023-07-10 17:00"),
                 file_tags: vec![
                     "testing".to_string(),
                     "rust".to_string(),
                     "orgmode".to_string(),
                     "linkorg".to_string(),
                 ],
+                file_extra: BTreeMap::new(),
+                file_error: None,
             },
             level: 0,
             heading: vec![
@@ -407,55 +927,86 @@ mod tests {
                     title: "Level 1 heading".to_string(),
                     level: 1,
                     line_number: 6,
-                    heading: vec![Heading {
-                        title: "Level 2 heading 1 under 1".to_string(),
-                        level: 2,
-                        line_number: 7,
-                        heading: vec![],
-                        links: vec![
-                            Link {
-                                name: "Table test book 1".to_string(),
-                                link: "Link to test book 1".to_string(),
-                                read_till: "20".to_string(),
-                                description: Some("Good book".to_string()),
-                                likeability: Some("Must read".to_string()),
-                                line_number: 8,
-                            },
-                            Link {
-                                name: "Table test book 2".to_string(),
-                                link: "Link to test book 2".to_string(),
-                                read_till: "2".to_string(),
-                                description: None,
-                                likeability: Some("Good read".to_string()),
-                                line_number: 9,
-                            },
-                        ],
-                    }],
-                    links: vec![],
-                },
-                Heading {
-                    title: "Level 2 heading 2 under 1".to_string(),
-                    level: 2,
-                    line_number: 10,
-                    heading: vec![],
-                    links: vec![
-                        Link {
-                            name: "Table test book 3".to_string(),
-                            link: "Link to test book 3".to_string(),
-                            read_till: "8".to_string(),
-                            description: Some("Mediocure book".to_string()),
-                            likeability: None,
-                            line_number: 11,
+                    heading: vec![
+                        Heading {
+                            title: "Level 2 heading 1 under 1".to_string(),
+                            level: 2,
+                            line_number: 7,
+                            heading: vec![],
+                            links: vec![
+                                Link {
+                                    name: "Table test book 1".to_string(),
+                                    link: "Link to test book 1".to_string(),
+                                    read_till: Some(Progress::Page(20)),
+                                    description: Some("Good book".to_string()),
+                                    likeability: likeability("Must read", Some(0)),
+                                    line_number: 8,
+                                    added: None,
+                                    finished: None,
+                                },
+                                Link {
+                                    name: "Table test book 2".to_string(),
+                                    link: "Link to test book 2".to_string(),
+                                    read_till: Some(Progress::Page(2)),
+                                    description: None,
+                                    likeability: likeability("Good read", Some(1)),
+                                    line_number: 9,
+                                    added: None,
+                                    finished: None,
+                                },
+                            ],
+                            properties: BTreeMap::new(),
+                            tags: vec![],
+                            inherited_tags: example_tags(),
+                            state: None,
+                            priority: None,
+                            scheduled: None,
+                            deadline: None,
                         },
-                        Link {
-                            name: "Table test book 4".to_string(),
-                            link: "Link to test book 4".to_string(),
-                            read_till: "9".to_string(),
-                            description: None,
-                            likeability: None,
-                            line_number: 12,
+                        Heading {
+                            title: "Level 2 heading 2 under 1".to_string(),
+                            level: 2,
+                            line_number: 10,
+                            heading: vec![],
+                            links: vec![
+                                Link {
+                                    name: "Table test book 3".to_string(),
+                                    link: "Link to test book 3".to_string(),
+                                    read_till: Some(Progress::Page(8)),
+                                    description: Some("Mediocure book".to_string()),
+                                    likeability: None,
+                                    line_number: 11,
+                                    added: None,
+                                    finished: None,
+                                },
+                                Link {
+                                    name: "Table test book 4".to_string(),
+                                    link: "Link to test book 4".to_string(),
+                                    read_till: Some(Progress::Page(9)),
+                                    description: None,
+                                    likeability: None,
+                                    line_number: 12,
+                                    added: None,
+                                    finished: None,
+                                },
+                            ],
+                            properties: BTreeMap::new(),
+                            tags: vec![],
+                            inherited_tags: example_tags(),
+                            state: None,
+                            priority: None,
+                            scheduled: None,
+                            deadline: None,
                         },
                     ],
+                    links: vec![],
+                    properties: BTreeMap::new(),
+                    tags: vec![],
+                    inherited_tags: example_tags(),
+                    state: None,
+                    priority: None,
+                    scheduled: None,
+                    deadline: None,
                 },
                 Heading {
                     title: "Level 1 Table heading".to_string(),
@@ -466,36 +1017,51 @@ mod tests {
                         Link {
                             name: "Table test book 1".to_string(),
                             link: "Link to test book 1".to_string(),
-                            read_till: "20".to_string(),
+                            read_till: Some(Progress::Page(20)),
                             description: Some("Good book".to_string()),
-                            likeability: Some("Must read".to_string()),
+                            likeability: likeability("Must read", Some(0)),
                             line_number: 15,
+                            added: None,
+                            finished: None,
                         },
                         Link {
                             name: "Table test book 2".to_string(),
                             link: "Link to test book 2".to_string(),
-                            read_till: "2".to_string(),
+                            read_till: Some(Progress::Page(2)),
                             description: None,
-                            likeability: Some("Good read".to_string()),
+                            likeability: likeability("Good read", Some(1)),
                             line_number: 16,
+                            added: None,
+                            finished: None,
                         },
                         Link {
                             name: "Table test book 3".to_string(),
                             link: "Link to test book 3".to_string(),
-                            read_till: "8".to_string(),
+                            read_till: Some(Progress::Page(8)),
                             description: Some("Mediocure book".to_string()),
                             likeability: None,
                             line_number: 17,
+                            added: None,
+                            finished: None,
                         },
                         Link {
                             name: "Table test book 4".to_string(),
                             link: "Link to test book 4".to_string(),
-                            read_till: "9".to_string(),
+                            read_till: Some(Progress::Page(9)),
                             description: None,
                             likeability: None,
                             line_number: 18,
+                            added: None,
+                            finished: None,
                         },
                     ],
+                    properties: BTreeMap::new(),
+                    tags: vec![],
+                    inherited_tags: example_tags(),
+                    state: None,
+                    priority: None,
+                    scheduled: None,
+                    deadline: None,
                 },
             ],
             links: vec![],
@@ -508,18 +1074,20 @@ mod tests {
     fn classifying_md_test_heading() {
         let regex_collection = &*RE_MD;
         assert_eq!(
-            classify_line(
-                &"## Testing".to_string(),
-                1,
-                regex_collection,
-                &FileType::Md
-            ),
+            classify_line("## Testing", 1, regex_collection, &RatingScale::default()),
             Some(LineType::Heading(Heading {
                 title: "Testing".to_string(),
                 level: 2,
                 line_number: 1,
                 heading: vec![],
                 links: vec![],
+                properties: BTreeMap::new(),
+                tags: vec![],
+                inherited_tags: vec![],
+                state: None,
+                priority: None,
+                scheduled: None,
+                deadline: None,
             }))
         );
     }
@@ -529,215 +1097,217 @@ mod tests {
         let regex_collection = &*RE_MD;
         assert_eq!(
             classify_line(
-                &"[Table test book](Link to test book) (Mediocure read)             -- after 8"
-                    .to_string(),
+                "[Table test book](Link to test book) (Mediocure read)             -- after 8",
                 1,
                 regex_collection,
-                &FileType::Md
+                &RatingScale::default()
             ),
-            Some(LineType::Link(Link {
+            Some(LineType::Links(vec![Link {
                 name: "Table test book".to_string(),
                 link: "Link to test book".to_string(),
-                read_till: "8".to_string(),
-                likeability: Some("Mediocure read".to_string()),
+                read_till: Some(Progress::Page(8)),
+                likeability: likeability("Mediocure read", None),
                 description: None,
                 line_number: 1,
-            }))
+                added: None,
+                finished: None,
+            }]))
         );
     }
 
     #[test]
     fn classifying_md_test_link_table() {
         let regex_collection = &*RE_MD;
-        assert_eq!(classify_line(&"| [Table test book](Link to test book) | | (Mediocure read)|             -- after 8 |".to_string(),1,regex_collection,&FileType::Md),Some(LineType::Link( Link{ 
+        assert_eq!(classify_line("| [Table test book](Link to test book) | | (Mediocure read)|             -- after 8 |",1,regex_collection, &RatingScale::default()),Some(LineType::Links(vec![Link { 
             name: "Table test book".to_string(),
             link: "Link to test book".to_string(),
-            read_till: "8".to_string(),
-            likeability: Some("Mediocure read".to_string()),
+            read_till: Some(Progress::Page(8)),
+            likeability: likeability("Mediocure read", None),
             description: None,
             line_number: 1,
-        })));
-    }
-
-    #[test]
-    fn classifying_md_test_metadata_title() {
-        let regex_collection = &*RE_MD;
-        assert_eq!(
-            classify_line(
-                &"title: testing title".to_string(),
-                1,
-                regex_collection,
-                &FileType::Md
-            ),
-            Some(LineType::MetaData(MetaData::Title(
-                "testing title".to_string()
-            )))
-        );
-    }
-
-    #[test]
-    fn classifying_md_test_metadata_description() {
-        let regex_collection = &*RE_MD;
-        assert_eq!(
-            classify_line(
-                &"description: testing description".to_string(),
-                1,
-                regex_collection,
-                &FileType::Md
-            ),
-            Some(LineType::MetaData(MetaData::Description(
-                "testing description".to_string()
-            )))
-        );
-    }
-
-    #[test]
-    fn classifying_md_test_metadata_date() {
-        let regex_collection = &*RE_MD;
-        assert_eq!(
-            classify_line(
-                &"date: [2023-07-10 Mon 17:00]".to_string(),
-                1,
-                regex_collection,
-                &FileType::Md
-            ),
-            Some(LineType::MetaData(MetaData::Date(
-                "2023-07-10 Mon 17:00".to_string()
-            )))
-        );
+            added: None,
+            finished: None,
+        }])));
     }
 
     #[test]
-    fn classifying_md_test_metadata_tags() {
-        let regex_collection = &*RE_MD;
-        assert_eq!(
-            classify_line(
-                &"filetags: :tag1:tag2:tag3:".to_string(),
-                1,
-                regex_collection,
-                &FileType::Md
-            ),
-            Some(LineType::MetaData(MetaData::Tags(vec![
-                "tag1".to_string(),
-                "tag2".to_string(),
-                "tag3".to_string()
-            ])))
-        );
+    fn classifying_md_test_metadata_in_body() {
+        for line in [
+            "title: testing title",
+            "Subtitle: foo",
+            "date: [2023-07-10 Mon 17:00]",
+        ] {
+            assert_eq!(
+                classify_line(line, 1, &RE_MD, &RatingScale::default()),
+                None
+            );
+        }
     }
 
     #[test]
     fn test_parsing_md() {
-        let parsed_input =
-            read_data_file(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../examples/test.md"));
+        let parsed_input = read_data_file(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../examples/test.md"),
+            &RatingScale::default(),
+        )
+        .unwrap();
 
         let expected_output = FileData {
             file_name: "test.md".to_string(),
             file_meta_data: FileMetaData {
                 file_title: "test title".to_string(),
                 file_description: "Test description to check parsing".to_string(),
-                file_date: "2023-07-10 Mon 17:00".to_string(),
+                file_date: NoteDate::parse("2023-07-10 17:00"),
                 file_tags: vec![
                     "testing".to_string(),
                     "rust".to_string(),
                     "orgmode".to_string(),
                     "linkorg".to_string(),
                 ],
+                file_extra: BTreeMap::new(),
+                file_error: None,
             },
             level: 0,
             heading: vec![
                 Heading {
                     title: "Level 1 heading".to_string(),
                     level: 1,
-                    line_number: 6,
-                    heading: vec![Heading {
-                        title: "Level 2 heading 1 under 1".to_string(),
-                        level: 2,
-                        line_number: 7,
-                        heading: vec![],
-                        links: vec![
-                            Link {
-                                name: "Table test book 1".to_string(),
-                                link: "Link to test book 1".to_string(),
-                                read_till: "20".to_string(),
-                                description: Some("Good book".to_string()),
-                                likeability: Some("Must read".to_string()),
-                                line_number: 8,
-                            },
-                            Link {
-                                name: "Table test book 2".to_string(),
-                                link: "Link to test book 2".to_string(),
-                                read_till: "2".to_string(),
-                                description: None,
-                                likeability: Some("Good read".to_string()),
-                                line_number: 9,
-                            },
-                        ],
-                    }],
-                    links: vec![],
-                },
-                Heading {
-                    title: "Level 2 heading 2 under 1".to_string(),
-                    level: 2,
-                    line_number: 10,
-                    heading: vec![],
-                    links: vec![
-                        Link {
-                            name: "Table test book 3".to_string(),
-                            link: "Link to test book 3".to_string(),
-                            read_till: "8".to_string(),
-                            description: Some("Mediocure book".to_string()),
-                            likeability: None,
-                            line_number: 11,
+                    line_number: 8,
+                    heading: vec![
+                        Heading {
+                            title: "Level 2 heading 1 under 1".to_string(),
+                            level: 2,
+                            line_number: 9,
+                            heading: vec![],
+                            links: vec![
+                                Link {
+                                    name: "Table test book 1".to_string(),
+                                    link: "Link to test book 1".to_string(),
+                                    read_till: Some(Progress::Page(20)),
+                                    description: Some("Good book".to_string()),
+                                    likeability: likeability("Must read", Some(0)),
+                                    line_number: 10,
+                                    added: None,
+                                    finished: None,
+                                },
+                                Link {
+                                    name: "Table test book 2".to_string(),
+                                    link: "Link to test book 2".to_string(),
+                                    read_till: Some(Progress::Page(2)),
+                                    description: None,
+                                    likeability: likeability("Good read", Some(1)),
+                                    line_number: 11,
+                                    added: None,
+                                    finished: None,
+                                },
+                            ],
+                            properties: BTreeMap::new(),
+                            tags: vec![],
+                            inherited_tags: example_tags(),
+                            state: None,
+                            priority: None,
+                            scheduled: None,
+                            deadline: None,
                         },
-                        Link {
-                            name: "Table test book 4".to_string(),
-                            link: "Link to test book 4".to_string(),
-                            read_till: "9".to_string(),
-                            description: None,
-                            likeability: None,
+                        Heading {
+                            title: "Level 2 heading 2 under 1".to_string(),
+                            level: 2,
                             line_number: 12,
+                            heading: vec![],
+                            links: vec![
+                                Link {
+                                    name: "Table test book 3".to_string(),
+                                    link: "Link to test book 3".to_string(),
+                                    read_till: Some(Progress::Page(8)),
+                                    description: Some("Mediocure book".to_string()),
+                                    likeability: None,
+                                    line_number: 13,
+                                    added: None,
+                                    finished: None,
+                                },
+                                Link {
+                                    name: "Table test book 4".to_string(),
+                                    link: "Link to test book 4".to_string(),
+                                    read_till: Some(Progress::Page(9)),
+                                    description: None,
+                                    likeability: None,
+                                    line_number: 14,
+                                    added: None,
+                                    finished: None,
+                                },
+                            ],
+                            properties: BTreeMap::new(),
+                            tags: vec![],
+                            inherited_tags: example_tags(),
+                            state: None,
+                            priority: None,
+                            scheduled: None,
+                            deadline: None,
                         },
                     ],
+                    links: vec![],
+                    properties: BTreeMap::new(),
+                    tags: vec![],
+                    inherited_tags: example_tags(),
+                    state: None,
+                    priority: None,
+                    scheduled: None,
+                    deadline: None,
                 },
                 Heading {
                     title: "Level 1 Table heading".to_string(),
                     level: 1,
-                    line_number: 13,
+                    line_number: 15,
                     heading: vec![],
                     links: vec![
                         Link {
                             name: "Table test book 1".to_string(),
                             link: "Link to test book 1".to_string(),
-                            read_till: "20".to_string(),
+                            read_till: Some(Progress::Page(20)),
                             description: Some("Good book".to_string()),
-                            likeability: Some("Must read".to_string()),
-                            line_number: 15,
+                            likeability: likeability("Must read", Some(0)),
+                            line_number: 17,
+                            added: None,
+                            finished: None,
                         },
                         Link {
                             name: "Table test book 2".to_string(),
                             link: "Link to test book 2".to_string(),
-                            read_till: "2".to_string(),
+                            read_till: Some(Progress::Page(2)),
                             description: None,
-                            likeability: Some("Good read".to_string()),
-                            line_number: 16,
+                            likeability: likeability("Good read", Some(1)),
+                            line_number: 18,
+                            added: None,
+                            finished: None,
                         },
                         Link {
                             name: "Table test book 3".to_string(),
                             link: "Link to test book 3".to_string(),
-                            read_till: "8".to_string(),
+                            read_till: Some(Progress::Page(8)),
                             description: Some("Mediocure book".to_string()),
                             likeability: None,
-                            line_number: 17,
+                            line_number: 19,
+                            added: None,
+                            finished: None,
                         },
                         Link {
                             name: "Table test book 4".to_string(),
                             link: "Link to test book 4".to_string(),
-                            read_till: "9".to_string(),
+                            read_till: Some(Progress::Page(9)),
                             description: None,
                             likeability: None,
-                            line_number: 18,
+                            line_number: 20,
+                            added: None,
+                            finished: None,
                         },
                     ],
+                    properties: BTreeMap::new(),
+                    tags: vec![],
+                    inherited_tags: example_tags(),
+                    state: None,
+                    priority: None,
+                    scheduled: None,
+                    deadline: None,
                 },
             ],
             links: vec![],
@@ -745,4 +1315,375 @@ mod tests {
 
         assert_eq!(parsed_input, expected_output);
     }
+
+    #[test]
+    fn reading_unsupported_file() {
+        let parsed_input = read_data_file(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"),
+            &RatingScale::default(),
+        );
+        assert!(matches!(
+            parsed_input,
+            Err(LinkorgError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn reading_missing_file() {
+        let parsed_input = read_data_file(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("missing.org"),
+            &RatingScale::default(),
+        );
+        assert!(matches!(parsed_input, Err(LinkorgError::Io(_, _))));
+    }
+
+    #[test]
+    fn reading_invalid_utf8_file() {
+        let path = std::env::temp_dir().join("linkorg_reading_invalid_utf8_file.org");
+        std::fs::write(&path, b"* Heading\n\xff\xfe\n").unwrap();
+
+        let parsed_input = read_data_file(path.clone(), &RatingScale::default());
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(parsed_input, Err(LinkorgError::InvalidUtf8(_, 2))));
+    }
+
+    #[test]
+    fn parsing_heading_level_jumps() {
+        let path = std::env::temp_dir().join("linkorg_parsing_heading_level_jumps.org");
+        std::fs::write(&path, "* One\n*** Three\n** Two\n* Other one\n").unwrap();
+
+        let parsed_input = read_data_file(path.clone(), &RatingScale::default()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let titles: Vec<(&str, Vec<&str>)> = parsed_input
+            .heading
+            .iter()
+            .map(|heading| {
+                (
+                    heading.title.as_str(),
+                    heading
+                        .heading
+                        .iter()
+                        .map(|nested| nested.title.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            vec![("One", vec!["Three", "Two"]), ("Other one", vec![])]
+        );
+    }
+
+    #[test]
+    fn parsing_org_keywords_and_properties() {
+        let lines: Vec<String> = "#+TITLE: Books\n#+AUTHOR: Someone\n#+category: reading\n:PROPERTIES:\n:ID: file-id\n:END:\n* Kafka\n:PROPERTIES:\n:ID: kafka\n:Source:   library\n:END:\n[[Link][Name]]\n** Chapters\n:PROPERTIES:\n:ORDERED: t\n:END:"
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        let data = Org.parse("test.org".to_string(), &lines, &RatingScale::default());
+
+        let extra = &data.file_meta_data.file_extra;
+        assert_eq!(data.file_meta_data.file_title, "Books");
+        assert_eq!(extra.get("author").unwrap(), "Someone");
+        assert_eq!(extra.get("category").unwrap(), "reading");
+        assert_eq!(extra.get("id").unwrap(), "file-id");
+
+        let kafka = &data.heading[0];
+        assert_eq!(
+            kafka.properties,
+            BTreeMap::from([
+                ("ID".to_string(), "kafka".to_string()),
+                ("Source".to_string(), "library".to_string()),
+            ])
+        );
+        assert_eq!(kafka.links.len(), 1);
+        assert_eq!(
+            kafka.heading[0].properties,
+            BTreeMap::from([("ORDERED".to_string(), "t".to_string())])
+        );
+    }
+
+    #[test]
+    fn ignoring_properties_outside_properties_drawer() {
+        let lines: Vec<String> = ":note: not a property
+* Kafka
+:LOGBOOK:
+:clock: 1h
+:END:
+See :page: 20
+:Source: library
+:PROPERTIES:
+:ID: kafka
+:END:
+:after: end"
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        let data = Org.parse("test.org".to_string(), &lines, &RatingScale::default());
+
+        assert!(!data.file_meta_data.file_extra.contains_key("note"));
+        assert_eq!(
+            data.heading[0].properties,
+            BTreeMap::from([("ID".to_string(), "kafka".to_string())])
+        );
+    }
+
+    #[test]
+    fn parsing_link_dates() {
+        let regex_collection = &*RE_ORG;
+        let links =
+            |line: &str| match classify_line(line, 1, regex_collection, &RatingScale::default()) {
+                Some(LineType::Links(links)) => links,
+                val => panic!("Not a link line {:?}", val),
+            };
+
+        let link = &links("[[Link][Name]] (Must read) -- added 2024-01-01")[0];
+        assert_eq!(link.added, NoteDate::parse("2024-01-01"));
+        assert_eq!(link.likeability.as_ref().unwrap().label, "Must read");
+
+        let link = &links("[[Link][Name]] [2024-01-01 Mon] -- done [2024-02-03 Sat 20:00]")[0];
+        assert_eq!(link.added, NoteDate::parse("2024-01-01"));
+        assert_eq!(link.finished, NoteDate::parse("2024-02-03 20:00"));
+        assert_eq!(link.read_till, Some(Progress::Done));
+
+        let both = links("[[One]] -- added 2024-01-01 [[Two]] -- after 3");
+        assert_eq!(both[0].added, NoteDate::parse("2024-01-01"));
+        assert_eq!(both[1].added, None);
+        assert_eq!(both[1].finished, None);
+    }
+
+    #[test]
+    fn parsing_progress() {
+        assert_eq!(Progress::parse("20"), Progress::Page(20));
+        assert_eq!(Progress::parse("37.5%"), Progress::Percentage(37.5));
+        assert_eq!(Progress::parse("NaN%"), Progress::Other("NaN%".to_string()));
+        assert_eq!(
+            Progress::parse("chapter 3"),
+            Progress::Chapter("3".to_string())
+        );
+        assert_eq!(
+            Progress::parse("ch4.2"),
+            Progress::Chapter("4.2".to_string())
+        );
+        assert_eq!(Progress::parse("1:02:03"), Progress::Timestamp(3723));
+        assert_eq!(Progress::parse("12:30"), Progress::Timestamp(750));
+        assert_eq!(Progress::parse("Done"), Progress::Done);
+        assert_eq!(Progress::parse("not  started"), Progress::NotStarted);
+        assert_eq!(
+            Progress::parse("intro"),
+            Progress::Other("intro".to_string())
+        );
+    }
+
+    #[test]
+    fn classifying_org_test_link_progress() {
+        let regex_collection = &*RE_ORG;
+        for (annotation, progress) in [
+            ("-- after 45%", Progress::Percentage(45.0)),
+            ("-- after chapter 7", Progress::Chapter("7".to_string())),
+            ("-- after 1:05:00", Progress::Timestamp(3900)),
+            ("-- done", Progress::Done),
+            ("-- not started", Progress::NotStarted),
+        ] {
+            let line = format!("[[Link][Name]] (Good read) {}", annotation);
+            match classify_line(&line, 1, regex_collection, &RatingScale::default()) {
+                Some(LineType::Links(links)) => {
+                    assert_eq!(links[0].read_till, Some(progress.clone()));
+                    assert_eq!(progress.annotation(), annotation);
+                }
+                val => panic!("Not a link line {:?}", val),
+            }
+        }
+    }
+
+    #[test]
+    fn classifying_org_test_link_custom_ratings() {
+        let ratings = RatingScale(vec![
+            "Loved".to_string(),
+            "Liked".to_string(),
+            "Meh".to_string(),
+        ]);
+        assert_eq!(
+            classify_line(
+                "[[Link][Name]] (Some thoughts) (liked)",
+                1,
+                &RE_ORG,
+                &ratings
+            ),
+            Some(LineType::Links(vec![Link {
+                name: "Name".to_string(),
+                link: "Link".to_string(),
+                read_till: None,
+                likeability: likeability("liked", Some(1)),
+                description: Some("Some thoughts".to_string()),
+                line_number: 1,
+                added: None,
+                finished: None,
+            }]))
+        );
+    }
+
+    fn parse_example(name: &str) -> FileData {
+        read_data_file(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("../examples")
+                .join(name),
+            &RatingScale::default(),
+        )
+        .unwrap()
+    }
+
+    // The parts of a note which don't depend on the format it is written in.
+    fn outline(mut data: FileData) -> FileData {
+        fn strip(headings: &mut [Heading], links: &mut [Link]) {
+            for link in links {
+                link.link = String::new();
+                link.line_number = 0;
+            }
+            for heading in headings {
+                heading.line_number = 0;
+                strip(&mut heading.heading, &mut heading.links);
+            }
+        }
+        data.file_name = String::new();
+        strip(&mut data.heading, &mut data.links);
+        data
+    }
+
+    #[test]
+    fn classifying_adoc_test_heading() {
+        assert_eq!(
+            classify_line("=== Testing", 1, &RE_ADOC, &RatingScale::default()),
+            Some(LineType::Heading(Heading {
+                title: "Testing".to_string(),
+                level: 2,
+                line_number: 1,
+                heading: vec![],
+                links: vec![],
+                properties: BTreeMap::new(),
+                tags: vec![],
+                inherited_tags: vec![],
+                state: None,
+                priority: None,
+                scheduled: None,
+                deadline: None,
+            }))
+        );
+        assert_eq!(
+            classify_line("= Document", 1, &RE_ADOC, &RatingScale::default()),
+            Some(LineType::MetaData(MetaData::Title("Document".to_string())))
+        );
+    }
+
+    #[test]
+    fn classifying_adoc_test_links() {
+        let links: Vec<_> = find_links(
+            "link:notes/book.html[Book] (Must read) and https://example.com[] -- after 3",
+            1,
+            &RE_ADOC,
+            &RatingScale::default(),
+        )
+        .into_iter()
+        .map(|span| span.link)
+        .collect();
+        assert_eq!(
+            links,
+            vec![
+                Link {
+                    name: "Book".to_string(),
+                    link: "notes/book.html".to_string(),
+                    read_till: None,
+                    description: None,
+                    likeability: likeability("Must read", Some(0)),
+                    line_number: 1,
+                    added: None,
+                    finished: None,
+                },
+                Link {
+                    name: "https://example.com".to_string(),
+                    link: "https://example.com".to_string(),
+                    read_till: Some(Progress::Page(3)),
+                    description: None,
+                    likeability: None,
+                    line_number: 1,
+                    added: None,
+                    finished: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parsing_adoc() {
+        let parsed_input = parse_example("test.adoc");
+        assert_eq!(parsed_input.file_name, "test.adoc");
+        assert_eq!(parsed_input.heading[0].heading[0].line_number, 7);
+        assert_eq!(
+            parsed_input.heading[0].heading[0].links[1].link,
+            "https://example.com/test-book-2"
+        );
+        assert_eq!(outline(parsed_input), outline(parse_example("test.org")));
+    }
+
+    #[test]
+    fn classifying_rst_test_heading() {
+        let classify = |line, next| Rst.classify_with_next(line, next, 1, &RatingScale::default());
+        assert_eq!(
+            classify("Testing", Some("-------")),
+            Some(LineType::Heading(Heading {
+                title: "Testing".to_string(),
+                level: 2,
+                line_number: 1,
+                heading: vec![],
+                links: vec![],
+                properties: BTreeMap::new(),
+                tags: vec![],
+                inherited_tags: vec![],
+                state: None,
+                priority: None,
+                scheduled: None,
+                deadline: None,
+            }))
+        );
+        assert_eq!(classify("Testing", Some("---")), None);
+        assert_eq!(classify("Testing", None), None);
+        assert_eq!(classify("=======", Some("=======")), None);
+        assert_eq!(
+            classify(".. title:: Document", Some("")),
+            Some(LineType::MetaData(MetaData::Title("Document".to_string())))
+        );
+    }
+
+    #[test]
+    fn classifying_rst_test_link() {
+        assert_eq!(
+            classify_line(
+                "`Name <Link>`_ (Good book) (Must read) -- after 20",
+                1,
+                &RE_RST,
+                &RatingScale::default()
+            ),
+            Some(LineType::Links(vec![Link {
+                name: "Name".to_string(),
+                link: "Link".to_string(),
+                read_till: Some(Progress::Page(20)),
+                description: Some("Good book".to_string()),
+                likeability: likeability("Must read", Some(0)),
+                line_number: 1,
+                added: None,
+                finished: None,
+            }]))
+        );
+    }
+
+    #[test]
+    fn test_parsing_rst() {
+        let parsed_input = parse_example("test.rst");
+        assert_eq!(parsed_input.file_name, "test.rst");
+        assert_eq!(parsed_input.heading[1].line_number, 24);
+        assert_eq!(parsed_input.heading[1].links[3].line_number, 34);
+        assert_eq!(outline(parsed_input), outline(parse_example("test.org")));
+    }
 }
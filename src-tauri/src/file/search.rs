@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::parse::{FileData, FileMetaData, Heading, Link};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum SearchField {
+    Name,
+    Link,
+    Description,
+    Heading,
+    Title,
+    FileDescription,
+    Tags,
+}
+
+// A link, heading or file whose fields together contain every term of the
+// query. `fields` lists the ones which matched, metadata hits have no line to
+// jump to.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct SearchHit {
+    pub file: PathBuf,
+    pub breadcrumb: Vec<String>,
+    pub line_number: Option<usize>,
+    pub text: String,
+    pub fields: Vec<SearchField>,
+}
+
+// A link together with the note and headings it is in, and every tag it
+// carries from them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LinkEntry {
+    pub file: PathBuf,
+    pub breadcrumb: Vec<String>,
+    pub tags: Vec<String>,
+    pub link: Link,
+}
+
+struct Query {
+    terms: Vec<String>,
+}
+
+impl Query {
+    fn new(query: &str) -> Query {
+        Query {
+            terms: query
+                .split_whitespace()
+                .map(|term| term.to_lowercase())
+                .collect(),
+        }
+    }
+
+    fn matches(&self, fields: &[(SearchField, &str)]) -> Option<Vec<SearchField>> {
+        if self.terms.is_empty() {
+            return None;
+        }
+        let fields: Vec<(SearchField, String)> = fields
+            .iter()
+            .map(|(field, text)| (*field, text.to_lowercase()))
+            .collect();
+        let found = |text: &String| self.terms.iter().any(|term| text.contains(term));
+        let all_found = self
+            .terms
+            .iter()
+            .all(|term| fields.iter().any(|(_, text)| text.contains(term)));
+        if !all_found {
+            return None;
+        }
+        Some(
+            fields
+                .iter()
+                .filter(|(_, text)| found(text))
+                .map(|(field, _)| *field)
+                .collect(),
+        )
+    }
+}
+
+fn search_links(
+    query: &Query,
+    file: &Path,
+    breadcrumb: &[String],
+    links: &[Link],
+    hits: &mut Vec<SearchHit>,
+) {
+    for link in links {
+        let mut fields = vec![
+            (SearchField::Name, link.name.as_str()),
+            (SearchField::Link, link.link.as_str()),
+        ];
+        if let Some(description) = &link.description {
+            fields.push((SearchField::Description, description));
+        }
+        if let Some(fields) = query.matches(&fields) {
+            hits.push(SearchHit {
+                file: file.to_path_buf(),
+                breadcrumb: breadcrumb.to_vec(),
+                line_number: Some(link.line_number),
+                text: link.name.clone(),
+                fields,
+            });
+        }
+    }
+}
+
+fn search_headings(
+    query: &Query,
+    file: &Path,
+    breadcrumb: &mut Vec<String>,
+    headings: &[Heading],
+    hits: &mut Vec<SearchHit>,
+) {
+    for heading in headings {
+        let tags = heading.tags.join(" ");
+        if let Some(fields) = query.matches(&[
+            (SearchField::Heading, &heading.title),
+            (SearchField::Tags, &tags),
+        ]) {
+            hits.push(SearchHit {
+                file: file.to_path_buf(),
+                breadcrumb: breadcrumb.clone(),
+                line_number: Some(heading.line_number),
+                text: heading.title.clone(),
+                fields,
+            });
+        }
+        breadcrumb.push(heading.title.clone());
+        search_links(query, file, breadcrumb, &heading.links, hits);
+        search_headings(query, file, breadcrumb, &heading.heading, hits);
+        breadcrumb.pop();
+    }
+}
+
+fn search_file_data(query: &Query, file: &Path, data: &FileData) -> Vec<SearchHit> {
+    let mut hits = vec![];
+
+    // The placeholders of missing metadata are not part of the note.
+    let placeholder = FileMetaData::default();
+    let meta_data = &data.file_meta_data;
+    let tags = meta_data.file_tags.join(" ");
+    let mut fields = vec![(SearchField::Tags, tags.as_str())];
+    if meta_data.file_title != placeholder.file_title {
+        fields.push((SearchField::Title, &meta_data.file_title));
+    }
+    if meta_data.file_description != placeholder.file_description {
+        fields.push((SearchField::FileDescription, &meta_data.file_description));
+    }
+    if let Some(fields) = query.matches(&fields) {
+        hits.push(SearchHit {
+            file: file.to_path_buf(),
+            breadcrumb: vec![],
+            line_number: None,
+            text: if meta_data.file_title != placeholder.file_title {
+                meta_data.file_title.clone()
+            } else {
+                data.file_name.clone()
+            },
+            fields,
+        });
+    }
+
+    search_links(query, file, &[], &data.links, &mut hits);
+    search_headings(query, file, &mut vec![], &data.heading, &mut hits);
+    hits
+}
+
+// Every link, heading and file of the notes matching all the whitespace
+// separated terms of `query`, ignoring case.
+pub fn search_notes<'a>(
+    notes: impl IntoIterator<Item = (&'a Path, &'a FileData)>,
+    query: &str,
+) -> Vec<SearchHit> {
+    let query = Query::new(query);
+    notes
+        .into_iter()
+        .flat_map(|(file, data)| search_file_data(&query, file, data))
+        .collect()
+}
+
+fn collect_links(
+    file: &Path,
+    breadcrumb: &mut Vec<String>,
+    headings: &[Heading],
+    entries: &mut Vec<LinkEntry>,
+) {
+    for heading in headings {
+        let mut tags = heading.inherited_tags.clone();
+        tags.extend(
+            heading
+                .tags
+                .iter()
+                .filter(|tag| !heading.inherited_tags.contains(tag))
+                .cloned(),
+        );
+        breadcrumb.push(heading.title.clone());
+        entries.extend(heading.links.iter().map(|link| LinkEntry {
+            file: file.to_path_buf(),
+            breadcrumb: breadcrumb.clone(),
+            tags: tags.clone(),
+            link: link.clone(),
+        }));
+        collect_links(file, breadcrumb, &heading.heading, entries);
+        breadcrumb.pop();
+    }
+}
+
+// Every link of the notes, in the order they are written in.
+pub fn note_links<'a>(notes: impl IntoIterator<Item = (&'a Path, &'a FileData)>) -> Vec<LinkEntry> {
+    let mut entries = vec![];
+    for (file, data) in notes {
+        entries.extend(data.links.iter().map(|link| LinkEntry {
+            file: file.to_path_buf(),
+            breadcrumb: vec![],
+            tags: data.file_meta_data.file_tags.clone(),
+            link: link.clone(),
+        }));
+        collect_links(file, &mut vec![], &data.heading, &mut entries);
+    }
+    entries
+}
+
+// Every link carrying `tag` from its file or one of its headings, ignoring case.
+pub fn links_with_tag<'a>(
+    notes: impl IntoIterator<Item = (&'a Path, &'a FileData)>,
+    tag: &str,
+) -> Vec<LinkEntry> {
+    note_links(notes)
+        .into_iter()
+        .filter(|entry| entry.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::RatingScale;
+    use crate::file::format::{org::Org, NoteFormat};
+    use crate::file::parse::read_data_file;
+
+    fn search_test_org(query: &str) -> Vec<SearchHit> {
+        let file = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../examples/test.org");
+        let data = read_data_file(file.clone(), &RatingScale::default()).unwrap();
+        search_notes([(file.as_path(), &data)], query)
+    }
+
+    #[test]
+    fn searching_link_names_with_breadcrumb() {
+        let hits = search_test_org("TEST BOOK 4");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(
+            hits[0].breadcrumb,
+            vec![
+                "Level 1 heading".to_string(),
+                "Level 2 heading 2 under 1".to_string()
+            ]
+        );
+        assert_eq!(hits[0].line_number, Some(12));
+        assert_eq!(hits[0].fields, vec![SearchField::Name, SearchField::Link]);
+        assert_eq!(
+            hits[1].breadcrumb,
+            vec!["Level 1 Table heading".to_string()]
+        );
+        assert_eq!(hits[1].line_number, Some(18));
+    }
+
+    #[test]
+    fn searching_terms_across_fields() {
+        let hits = search_test_org("mediocure book 3");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(
+            hits[0].fields,
+            vec![
+                SearchField::Name,
+                SearchField::Link,
+                SearchField::Description
+            ]
+        );
+    }
+
+    #[test]
+    fn searching_headings_and_metadata() {
+        let hits = search_test_org("table heading");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, "Level 1 Table heading");
+        assert_eq!(hits[0].breadcrumb, Vec::<String>::new());
+        assert_eq!(hits[0].line_number, Some(13));
+
+        let hits = search_test_org("orgmode");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, "test title");
+        assert_eq!(hits[0].line_number, None);
+        assert_eq!(hits[0].fields, vec![SearchField::Tags]);
+    }
+
+    #[test]
+    fn finding_links_by_inherited_tags() {
+        let file = PathBuf::from("/notes/books.org");
+        let lines: Vec<String> = "#+filetags: :reading:\n[[Link 0][Top]]\n* Business :finance:mgmt:\n[[Link 1][Name 1]]\n** Accounting :finance:tax:\n[[Link 2][Name 2]]\n* Fiction\n[[Link 3][Name 3]]"
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        let data = Org.parse("books.org".to_string(), &lines, &RatingScale::default());
+        assert_eq!(data.heading[0].title, "Business");
+        assert_eq!(data.heading[0].tags, vec!["finance", "mgmt"]);
+        assert_eq!(
+            data.heading[0].heading[0].inherited_tags,
+            vec!["reading", "finance", "mgmt"]
+        );
+
+        let names = |tag: &str| -> Vec<String> {
+            links_with_tag([(file.as_path(), &data)], tag)
+                .into_iter()
+                .map(|entry| entry.link.name)
+                .collect()
+        };
+        assert_eq!(names("Finance"), vec!["Name 1", "Name 2"]);
+        assert_eq!(names("tax"), vec!["Name 2"]);
+        assert_eq!(names("reading").len(), 4);
+        assert!(names("fiction").is_empty());
+
+        let entries = links_with_tag([(file.as_path(), &data)], "tax");
+        assert_eq!(entries[0].breadcrumb, vec!["Business", "Accounting"]);
+        assert_eq!(entries[0].tags, vec!["reading", "finance", "mgmt", "tax"]);
+    }
+
+    #[test]
+    fn searching_without_terms() {
+        assert!(search_test_org("   ").is_empty());
+        assert!(search_test_org("no such thing").is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+use notify::{
+    event::ModifyKind, recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode,
+    Watcher,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{config::config::Source, error::LinkorgError};
+
+use super::{file::is_source_note, parse::FileData};
+
+#[derive(Debug, PartialEq)]
+pub enum NotesChange {
+    // The content of a note may have changed.
+    Modified(PathBuf),
+    // A note was created, removed or renamed.
+    ListChanged,
+}
+
+// Payload of the event sent to the frontend when an open note changed.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FileChange {
+    pub file: PathBuf,
+    pub data: FileData,
+}
+
+// The changes to the notes of `sources`, leaving out every file which
+// find_data_files wouldn't list either.
+pub(crate) fn note_changes(event: &Event, sources: &[Source]) -> Vec<NotesChange> {
+    let notes: Vec<&PathBuf> = event
+        .paths
+        .iter()
+        .filter(|path| sources.iter().any(|source| is_source_note(source, path)))
+        .collect();
+    if notes.is_empty() {
+        return vec![];
+    }
+
+    let mut changes = vec![];
+    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        changes.extend(
+            notes
+                .into_iter()
+                .map(|path| NotesChange::Modified(path.clone())),
+        );
+    }
+    if matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+    ) {
+        changes.push(NotesChange::ListChanged);
+    }
+    changes
+}
+
+// Watches every note of the `sources`, the returned watcher stops once it is
+// dropped. Editors often save through a temporary file, so one save can show up
+// as several changes. Every source is watched on its own, so one which can't be
+// watched is returned with the errors while the others are still watched. There
+// is no watcher when none of them could be watched.
+pub fn watch_notes<F>(
+    sources: &[Source],
+    mut on_change: F,
+) -> (Option<RecommendedWatcher>, Vec<LinkorgError>)
+where
+    F: FnMut(NotesChange) + Send + 'static,
+{
+    let watched = sources.to_vec();
+    let watcher = recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event {
+            for change in note_changes(&event, &watched) {
+                on_change(change);
+            }
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            let notes_dir = sources
+                .first()
+                .map(|source| source.path.clone())
+                .unwrap_or_default();
+            return (None, vec![LinkorgError::Watch(notes_dir, err.to_string())]);
+        }
+    };
+
+    let mut errors = vec![];
+    for source in sources {
+        if let Err(err) = watcher.watch(&source.path, RecursiveMode::Recursive) {
+            errors.push(LinkorgError::Watch(source.path.clone(), err.to_string()));
+        }
+    }
+    let watching = errors.len() < sources.len();
+    (Some(watcher).filter(|_| watching), errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RemoveKind, RenameMode};
+
+    #[test]
+    fn classifying_note_events() {
+        let sources = [Source::new("notes", PathBuf::from("/notes"))];
+        let note = PathBuf::from("/notes/books.org");
+        let event = Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            .add_path(note.clone());
+        assert_eq!(
+            note_changes(&event, &sources),
+            vec![NotesChange::Modified(note.clone())]
+        );
+
+        let event = Event::new(EventKind::Create(CreateKind::File)).add_path(note.clone());
+        assert_eq!(
+            note_changes(&event, &sources),
+            vec![
+                NotesChange::Modified(note.clone()),
+                NotesChange::ListChanged
+            ]
+        );
+
+        let event = Event::new(EventKind::Remove(RemoveKind::File)).add_path(note.clone());
+        assert_eq!(
+            note_changes(&event, &sources),
+            vec![NotesChange::ListChanged]
+        );
+
+        let event = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(note.clone())
+            .add_path(PathBuf::from("/notes/books.org~"));
+        assert_eq!(
+            note_changes(&event, &sources),
+            vec![NotesChange::Modified(note), NotesChange::ListChanged]
+        );
+    }
+
+    #[test]
+    fn watching_the_readable_dirs_only() {
+        let dir = std::env::temp_dir().join("linkorg_watching_the_readable_dirs_only");
+        std::fs::create_dir_all(&dir).unwrap();
+        let missing = dir.join("missing");
+
+        let sources = [
+            Source::new("missing", missing.clone()),
+            Source::new("notes", dir),
+        ];
+        let (watcher, errors) = watch_notes(&sources, |_| {});
+        assert!(watcher.is_some());
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], LinkorgError::Watch(path, _) if *path == missing));
+
+        let (watcher, errors) = watch_notes(&sources[..1], |_| {});
+        assert!(watcher.is_none());
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn ignoring_other_files() {
+        let mut source = Source::new("notes", PathBuf::from("/notes"));
+        source.exclude = vec!["drafts".to_string()];
+        let sources = [source];
+        let event = Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/notes/.#books.org"))
+            .add_path(PathBuf::from("/notes/image.png"))
+            .add_path(PathBuf::from("/notes/.git/books.org"))
+            .add_path(PathBuf::from("/notes/archive/old.org"))
+            .add_path(PathBuf::from("/notes/drafts/draft.org"))
+            .add_path(PathBuf::from("/elsewhere/books.org"));
+        assert_eq!(note_changes(&event, &sources), vec![]);
+    }
+}
@@ -2,16 +2,26 @@
 // #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod config;
+mod error;
 mod file;
 
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
 
 use crate::{
     config::config::{parse_config, Config},
-    file::{file::*, parse::*},
+    error::LinkorgError,
+    file::{
+        agenda::*, duplicates::*, edit::*, file::*, index::*, library::*, parse::*, search::*,
+        watch::*,
+    },
 };
 use dirs;
 use lazy_static::lazy_static;
+use tauri::{AppHandle, Manager, State};
 
 lazy_static! {
     pub static ref COMMON_FOLDER_NAME: &'static str = env!("CARGO_PKG_NAME");
@@ -19,21 +29,229 @@ lazy_static! {
         .expect("Unable to get Config Path")
         .join::<&'static str>(COMMON_FOLDER_NAME.as_ref())
         .join("config.toml");
+    pub static ref INDEX_PATH_GLOBAL: PathBuf = CONFIG_PATH_GLOBAL.with_file_name("index.json");
+}
+
+// The index only caches parsed files, so one left behind by a panicking
+// command is still good to use.
+fn lock_index<'a>(index: &'a State<'_, Mutex<Index>>) -> MutexGuard<'a, Index> {
+    index.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Re-reads the notes touched by the watcher and sends them to the frontend.
+fn notes_changed(app: &AppHandle, change: NotesChange) {
+    let Ok(config) = parse_config(&CONFIG_PATH_GLOBAL) else {
+        return;
+    };
+    match change {
+        NotesChange::Modified(file) => {
+            let index = app.state::<Mutex<Index>>();
+            let mut index = lock_index(&index);
+            // A note removed right after the change has nothing to send.
+            if let Ok(data) = index.file_data(&file, &config.ratings) {
+                let data = data.clone();
+                let _ = app.emit_all("file-changed", FileChange { file, data });
+            }
+            let _ = index.save(&INDEX_PATH_GLOBAL);
+        }
+        NotesChange::ListChanged => {
+            let _ = app.emit_all("files-list-changed", find_source_files(&config.sources));
+        }
+    }
+}
+
+// Why notes are not watched for changes, like a notes directory or the config
+// which can't be read, kept for the frontend to show.
+struct WatchErrors(Vec<String>);
+
+#[tauri::command]
+fn get_watch_errors(errors: State<'_, WatchErrors>) -> Vec<String> {
+    errors.0.clone()
 }
 
 #[tauri::command]
-fn get_config() -> Config {
+fn get_config() -> Result<Config, LinkorgError> {
     parse_config(&CONFIG_PATH_GLOBAL)
 }
 
 #[tauri::command]
-fn get_files_list(notes_dir: PathBuf) -> Vec<PathBuf> {
-    find_data_files(notes_dir)
+fn get_files_list() -> Result<Vec<SourceFiles>, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    Ok(find_source_files(&config.sources))
+}
+
+#[tauri::command]
+fn new_note(source: String, name: String) -> Result<PathBuf, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    let source = config
+        .sources
+        .iter()
+        .find(|other| other.name == source)
+        .ok_or(LinkorgError::UnknownSource(source))?;
+    create_note(source, &name)
+}
+
+#[tauri::command]
+fn get_file_data(file: PathBuf, index: State<'_, Mutex<Index>>) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    let mut index = lock_index(&index);
+    let data = index.file_data(&file, &config.ratings)?.clone();
+    // Failing to write the cache doesn't stop the note from being shown.
+    let _ = index.save(&INDEX_PATH_GLOBAL);
+    Ok(data)
+}
+
+// Every note of the sources which could be listed.
+fn source_notes(config: &Config) -> Vec<PathBuf> {
+    find_source_files(&config.sources)
+        .into_iter()
+        .flat_map(|source| source.files)
+        .collect()
+}
+
+// The index brought up to date with every note of every source.
+fn refreshed_index<'a>(
+    index: &'a State<'_, Mutex<Index>>,
+) -> Result<MutexGuard<'a, Index>, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    let files = source_notes(&config);
+    let mut index = lock_index(index);
+    index.refresh(&files, &config.ratings);
+    let _ = index.save(&INDEX_PATH_GLOBAL);
+    Ok(index)
+}
+
+#[tauri::command]
+fn search(query: String, index: State<'_, Mutex<Index>>) -> Result<Vec<SearchHit>, LinkorgError> {
+    let index = refreshed_index(&index)?;
+    Ok(search_notes(index.notes(), &query))
+}
+
+#[tauri::command]
+fn get_links_with_tag(
+    tag: String,
+    index: State<'_, Mutex<Index>>,
+) -> Result<Vec<LinkEntry>, LinkorgError> {
+    let index = refreshed_index(&index)?;
+    Ok(links_with_tag(index.notes(), &tag))
+}
+
+#[tauri::command]
+fn get_agenda(index: State<'_, Mutex<Index>>) -> Result<Vec<AgendaItem>, LinkorgError> {
+    let index = refreshed_index(&index)?;
+    Ok(agenda(index.notes()))
+}
+
+#[tauri::command]
+fn get_library(
+    sort: LinkSort,
+    descending: bool,
+    offset: usize,
+    limit: Option<usize>,
+    index: State<'_, Mutex<Index>>,
+) -> Result<LinkPage, LinkorgError> {
+    let index = refreshed_index(&index)?;
+    Ok(library_page(index.notes(), sort, descending, offset, limit))
+}
+
+#[tauri::command]
+fn get_duplicates(index: State<'_, Mutex<Index>>) -> Result<Vec<DuplicateCluster>, LinkorgError> {
+    let index = refreshed_index(&index)?;
+    Ok(find_duplicates(index.notes()))
+}
+
+#[tauri::command]
+fn update_link(file: PathBuf, link: Link, update: LinkUpdate) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    update_link_in_file(file, &link, &update, &config.ratings)
+}
+
+#[tauri::command]
+fn add_link(
+    file: PathBuf,
+    heading_line: Option<usize>,
+    link: LinkUpdate,
+) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    add_link_to_file(file, heading_line, &link, &config.ratings)
+}
+
+#[tauri::command]
+fn delete_link(file: PathBuf, link: Link) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    delete_link_from_file(file, &link, &config.ratings)
+}
+
+#[tauri::command]
+fn move_link(
+    file: PathBuf,
+    link: Link,
+    to_file: PathBuf,
+    heading_line: Option<usize>,
+) -> Result<BTreeMap<PathBuf, FileData>, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    let notes = source_notes(&config);
+    move_link_between_files(file, &link, to_file, heading_line, &notes, &config.ratings)
+}
+
+// The link addresses of `file`, or of every note when there is none, which
+// would be rewritten to their canonical form.
+#[tauri::command]
+fn preview_canonical_links(file: Option<PathBuf>) -> Result<Vec<UrlChange>, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    let files = match file {
+        Some(file) => vec![file],
+        None => source_notes(&config),
+    };
+    preview_canonical_urls(&files, &config.ratings)
+}
+
+#[tauri::command]
+fn canonicalise_links(
+    changes: Vec<UrlChange>,
+) -> Result<BTreeMap<PathBuf, FileData>, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    apply_url_changes(&changes, &source_notes(&config), &config.ratings)
+}
+
+#[tauri::command]
+fn add_heading(
+    file: PathBuf,
+    parent_line: Option<usize>,
+    title: String,
+) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    add_heading_to_file(file, parent_line, &title, &config.ratings)
+}
+
+#[tauri::command]
+fn rename_heading(
+    file: PathBuf,
+    heading_line: usize,
+    title: String,
+) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    rename_heading_in_file(file, heading_line, &title, &config.ratings)
+}
+
+#[tauri::command]
+fn relevel_heading(
+    file: PathBuf,
+    heading_line: usize,
+    shift: isize,
+) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    relevel_heading_in_file(file, heading_line, shift, &config.ratings)
 }
 
 #[tauri::command]
-fn get_file_data(file: PathBuf) -> FileData {
-    read_data_file(file)
+fn move_heading(
+    file: PathBuf,
+    heading_line: usize,
+    direction: Direction,
+) -> Result<FileData, LinkorgError> {
+    let config = parse_config(&CONFIG_PATH_GLOBAL)?;
+    move_heading_in_file(file, heading_line, direction, &config.ratings)
 }
 
 #[tauri::command]
@@ -43,11 +261,48 @@ fn greet(name: &str) -> String {
 
 fn main() {
     tauri::Builder::default()
+        .manage(Mutex::new(Index::load(&INDEX_PATH_GLOBAL)))
+        .setup(|app| {
+            let handle = app.handle();
+            // Without a watcher the app still works, it just doesn't see
+            // changes made outside of it. A config which can't be read leaves
+            // nothing to watch, its error is shown instead of stopping the app.
+            let (watcher, errors) = match parse_config(&CONFIG_PATH_GLOBAL) {
+                Ok(config) => watch_notes(&config.sources, move |change| {
+                    notes_changed(&handle, change)
+                }),
+                Err(err) => (None, vec![err]),
+            };
+            if let Some(watcher) = watcher {
+                app.manage(Mutex::new(watcher));
+            }
+            app.manage(WatchErrors(
+                errors.iter().map(|err| err.to_string()).collect(),
+            ));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             get_file_data,
             get_files_list,
-            get_config
+            new_note,
+            get_config,
+            get_watch_errors,
+            search,
+            get_links_with_tag,
+            get_agenda,
+            get_library,
+            get_duplicates,
+            update_link,
+            add_link,
+            delete_link,
+            move_link,
+            preview_canonical_links,
+            canonicalise_links,
+            add_heading,
+            rename_heading,
+            relevel_heading,
+            move_heading
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,25 @@
+import type { Progress } from "../types/data.d";
+
+// How far along a link is, as it is shown next to the link.
+export function progressLabel(progress?: Progress) {
+    switch (progress?.kind) {
+        case undefined:
+            return "";
+        case "NotStarted":
+            return "Not started";
+        case "Done":
+            return "Done";
+        case "Page":
+            return `Page ${progress.value}`;
+        case "Percentage":
+            return `${progress.value}%`;
+        case "Chapter":
+            return `Chapter ${progress.value}`;
+        case "Timestamp":
+            const minutes = Math.floor(progress.value / 60);
+            const seconds = String(progress.value % 60).padStart(2, "0");
+            return `${minutes}:${seconds}`;
+        case "Other":
+            return progress.value;
+    }
+}
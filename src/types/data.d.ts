@@ -3,7 +3,7 @@ export type FileData = {
   file_meta_data?: FileMetaData,
   file_title: string;
   file_description: string;
-  file_creation_date: string;
+  file_date?: string;
   file_tags: string[];
   heading: HeadingData[];
   links: LinkItem[];
@@ -12,22 +12,134 @@ export type FileData = {
 export type FileMetaData = {
    file_title?: string,
    file_description?: string,
-   file_creation_date?: string,
+   // ISO 8601, a range as `start/end`.
+   file_date?: string,
    file_tags: string[],
+   file_extra?: Record<string, unknown>,
+   // Why the metadata couldn't be read.
+   file_error?: string,
 }
 
+export type SourceFiles = {
+  name: string;
+  path: string;
+  files: string[];
+  // Why the notes of the source couldn't be listed.
+  error?: string;
+};
+
+export type FileChange = {
+  file: string;
+  data: FileData;
+};
+
 export type HeadingData = {
   title: string;
   level: number;
+  line_number: number;
   heading: HeadingData[];
   links: LinkData[];
+  properties?: Record<string, string>;
+  tags?: string[];
+  inherited_tags?: string[];
+  state?: TodoState;
+  priority?: string;
+  scheduled?: string;
+  deadline?: string;
+};
+
+export type TodoState = "Todo" | "Done";
+
+export type AgendaItem = {
+  file: string;
+  breadcrumb: string[];
+  title: string;
+  line_number: number;
+  priority?: string;
+  scheduled?: string;
+  deadline?: string;
+  links: LinkData[];
 };
 
 export type LinkData = {
   name: string;
   link: string;
   description: string;
-  likeability: string;
-  read_till: number;
+  likeability?: Likeability;
+  read_till?: Progress;
+  line_number: number;
+  // ISO 8601 dates.
+  added?: string;
+  finished?: string;
+};
+
+export type Likeability = {
+  label: string;
+  rank?: number;
+};
+export type LinkUpdate = {
+  name: string;
+  link: string;
+  description?: string;
+  likeability?: string;
+  read_till?: Progress;
+  // Filled in by the backend when left out: added for a new link and finished
+  // for one which is marked done.
+  added?: string;
+  finished?: string;
+};
+
+export type Progress =
+  | { kind: "NotStarted" }
+  | { kind: "Page"; value: number }
+  | { kind: "Percentage"; value: number }
+  | { kind: "Chapter"; value: string }
+  | { kind: "Timestamp"; value: number }
+  | { kind: "Done" }
+  | { kind: "Other"; value: string };
+
+export type SearchField =
+  | "Name"
+  | "Link"
+  | "Description"
+  | "Heading"
+  | "Title"
+  | "FileDescription"
+  | "Tags";
+
+export type LinkEntry = {
+  file: string;
+  breadcrumb: string[];
+  tags: string[];
+  link: LinkData;
+};
+
+export type SearchHit = {
+  file: string;
+  breadcrumb: string[];
+  line_number?: number;
+  text: string;
+  fields: SearchField[];
+};
+
+export type LinkSort = "Name" | "Rating" | "Progress" | "Date";
+
+export type LinkPage = {
+  // Links on all the pages.
+  total: number;
+  links: LinkEntry[];
+};
+
+export type DuplicateCluster = {
+  // The normalised address every link shares, missing when they were matched
+  // on their names.
+  url?: string;
+  links: LinkEntry[];
+};
+
+export type UrlChange = {
+  file: string;
   line_number: number;
-};
\ No newline at end of file
+  before: string;
+  after: string;
+};